@@ -28,6 +28,7 @@
             fileset = lib.fileset.unions [
               (craneLib.fileset.commonCargoSources ./.)
               (lib.fileset.maybeMissing ./assets)
+              (lib.fileset.maybeMissing ./systemd)
             ];
           };
           strictDeps = true;
@@ -48,6 +49,9 @@
             inherit cargoArtifacts;
             postInstall = ''
               wrapProgram "$out/bin/waystart" --prefix LD_LIBRARY_PATH : "${libPath}"
+              install -Dm644 systemd/* -t "$out/lib/systemd/user"
+              substituteInPlace "$out/lib/systemd/user/waystart@.service" \
+                --replace-fail /usr/bin/waystart "$out/bin/waystart"
             '';
           }
         );
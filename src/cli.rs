@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use xflags::xflags;
 
 xflags! {
@@ -5,7 +7,17 @@ xflags! {
 
     /// A start menu for Wayland-based window managers.
     cmd waystart {
-        default cmd standalone {}
+        /// Path of the IPC socket, defaults to $WAYSTART_SOCKET or a socket per Wayland display.
+        optional -s, --socket socket: PathBuf
+
+        default cmd standalone {
+            /// Output to open the window on: focused, cursor or an output name such as DP-1.
+            optional -o, --output output: String
+            /// Search term to prefill the search bar with.
+            optional -q, --query query: String
+            /// Only show results from one finder: desktop, math or wifi.
+            optional -m, --mode mode: String
+        }
 
         /// Start the Waystart daemon.
         cmd daemon {
@@ -14,13 +26,68 @@ xflags! {
         }
 
         /// Open the Waystart window.
-        cmd open {}
+        cmd open {
+            /// Output to open the window on: focused, cursor or an output name such as DP-1.
+            optional -o, --output output: String
+            /// Search term to prefill the search bar with.
+            optional -q, --query query: String
+            /// Only show results from one finder: desktop, math or wifi.
+            optional -m, --mode mode: String
+        }
 
         /// Close the Waystart window.
         cmd close {}
 
         /// Open/close the Waystart window.
-        cmd toggle {}
+        cmd toggle {
+            /// Output to open the window on: focused, cursor or an output name such as DP-1.
+            optional -o, --output output: String
+            /// Search term to prefill the search bar with.
+            optional -q, --query query: String
+            /// Only show results from one finder: desktop, math or wifi.
+            optional -m, --mode mode: String
+        }
+
+        /// Pick one of the lines read from stdin and print it, like dmenu.
+        cmd dmenu {
+            /// Allow choosing the search term itself when it isn't one of the lines.
+            optional -p, --print-query
+            /// Output to open the window on: focused, cursor or an output name such as DP-1.
+            optional -o, --output output: String
+        }
+
+        /// Rescan the applications and reload the config and favorites of the daemon.
+        cmd reload {}
+
+        /// Show an entry hidden from the results again.
+        cmd unhide {
+            /// Id of the entry, the name of its desktop file without the extension, such as firefox.
+            required id: String
+        }
+
+        /// Print the daemon's events as JSON lines until it exits.
+        cmd subscribe {}
+
+        /// Print the results of a search without opening the window.
+        cmd query {
+            /// Search term to look up.
+            required search_term: String
+            /// Only show results from one finder: desktop, math or wifi.
+            optional -m, --mode mode: String
+            /// Maximum number of results to print.
+            optional -n, --limit limit: usize
+            /// Print the results as JSON instead of tab-separated lines.
+            optional -j, --json
+        }
+
+        /// Manage the Waystart config.
+        cmd config {
+            /// Validate a config file and report its errors.
+            cmd check {
+                /// Path of the config file to check, defaults to the user's config.
+                optional path: PathBuf
+            }
+        }
     }
 }
 
@@ -29,6 +96,7 @@ xflags! {
 // Run `env UPDATE_XFLAGS=1 cargo build` to regenerate.
 #[derive(Debug)]
 pub struct Waystart {
+    pub socket: Option<PathBuf>,
     pub subcommand: WaystartCmd,
 }
 
@@ -39,10 +107,20 @@ pub enum WaystartCmd {
     Open(Open),
     Close(Close),
     Toggle(Toggle),
+    Dmenu(Dmenu),
+    Reload(Reload),
+    Unhide(Unhide),
+    Subscribe(Subscribe),
+    Query(Query),
+    Config(Config),
 }
 
 #[derive(Debug)]
-pub struct Standalone;
+pub struct Standalone {
+    pub output: Option<String>,
+    pub query: Option<String>,
+    pub mode: Option<String>,
+}
 
 #[derive(Debug)]
 pub struct Daemon {
@@ -50,13 +128,61 @@ pub struct Daemon {
 }
 
 #[derive(Debug)]
-pub struct Open;
+pub struct Open {
+    pub output: Option<String>,
+    pub query: Option<String>,
+    pub mode: Option<String>,
+}
 
 #[derive(Debug)]
 pub struct Close;
 
 #[derive(Debug)]
-pub struct Toggle;
+pub struct Toggle {
+    pub output: Option<String>,
+    pub query: Option<String>,
+    pub mode: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Dmenu {
+    pub print_query: bool,
+    pub output: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Reload;
+
+#[derive(Debug)]
+pub struct Unhide {
+    pub id: String,
+}
+
+#[derive(Debug)]
+pub struct Subscribe;
+
+#[derive(Debug)]
+pub struct Query {
+    pub search_term: String,
+    pub mode: Option<String>,
+    pub limit: Option<usize>,
+    pub json: bool,
+}
+
+#[derive(Debug)]
+pub struct Config {
+    pub subcommand: ConfigCmd,
+}
+
+#[derive(Debug)]
+pub enum ConfigCmd {
+    Check(Check),
+}
+
+#[derive(Debug)]
+pub struct Check {
+    pub path: Option<PathBuf>,
+}
 
 impl Waystart {
     #[allow(dead_code)]
@@ -1,18 +1,26 @@
-use std::path::PathBuf;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
 
-use gpui::{Global, Rgba, SharedString, rgb};
+use gpui::layer_shell::{Anchor, KeyboardInteractivity, Layer, LayerShellOptions};
+use gpui::{App, Global, Rgba, SharedString, px, rgb};
 use serde::Deserialize;
+use serde::de::DeserializeOwned;
 
-#[derive(Default, Deserialize)]
-#[serde(default)]
+use crate::ipc::events;
+use crate::ipc::protocol::Event;
+use crate::watcher;
+
+#[derive(Default, PartialEq)]
 pub struct Config {
     pub terminal: Option<String>,
 
     pub theme: ThemeConfig,
+
+    pub window: WindowConfig,
 }
 
-#[derive(Deserialize)]
+#[derive(PartialEq, Deserialize)]
 #[serde(default)]
 pub struct ThemeConfig {
     pub font_family: SharedString,
@@ -42,19 +50,373 @@ impl Default for ThemeConfig {
     }
 }
 
+#[derive(PartialEq, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    pub mode: WindowMode,
+    pub width: f32,
+    pub height: f32,
+    /// The output to open the window on.
+    pub output: WindowOutput,
+
+    /// The edges the layer-shell surface is attached to.
+    pub anchor: WindowAnchor,
+    /// The top, right, bottom and left margins of the layer-shell surface.
+    pub margin: [f32; 4],
+    pub layer: WindowLayer,
+    pub keyboard_interactivity: WindowKeyboardInteractivity,
+    pub namespace: String,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            mode: WindowMode::Popup,
+            width: 800.,
+            height: 500.,
+            output: WindowOutput::Default,
+
+            anchor: WindowAnchor::Center,
+            margin: [0.; 4],
+            layer: WindowLayer::Overlay,
+            keyboard_interactivity: WindowKeyboardInteractivity::Exclusive,
+            namespace: "waystart".to_string(),
+        }
+    }
+}
+
+impl WindowConfig {
+    pub fn layer_shell_options(&self) -> LayerShellOptions {
+        let [top, right, bottom, left] = self.margin;
+
+        LayerShellOptions {
+            namespace: self.namespace.clone(),
+            layer: match self.layer {
+                WindowLayer::Background => Layer::Background,
+                WindowLayer::Bottom => Layer::Bottom,
+                WindowLayer::Top => Layer::Top,
+                WindowLayer::Overlay => Layer::Overlay,
+            },
+            anchor: match self.anchor {
+                WindowAnchor::Center => Anchor::empty(),
+                WindowAnchor::Top => Anchor::TOP,
+                WindowAnchor::Bottom => Anchor::BOTTOM,
+                WindowAnchor::Left => Anchor::LEFT,
+                WindowAnchor::Right => Anchor::RIGHT,
+                WindowAnchor::TopLeft => Anchor::TOP | Anchor::LEFT,
+                WindowAnchor::TopRight => Anchor::TOP | Anchor::RIGHT,
+                WindowAnchor::BottomLeft => Anchor::BOTTOM | Anchor::LEFT,
+                WindowAnchor::BottomRight => Anchor::BOTTOM | Anchor::RIGHT,
+            },
+            margin: Some((px(top), px(right), px(bottom), px(left))),
+            keyboard_interactivity: match self.keyboard_interactivity {
+                WindowKeyboardInteractivity::None => KeyboardInteractivity::None,
+                WindowKeyboardInteractivity::Exclusive => KeyboardInteractivity::Exclusive,
+                WindowKeyboardInteractivity::OnDemand => KeyboardInteractivity::OnDemand,
+            },
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowMode {
+    /// A regular window, placed by the compositor.
+    Popup,
+    /// A wlr-layer-shell surface, drawn as an overlay.
+    LayerShell,
+}
+
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(from = "String")]
+pub enum WindowOutput {
+    /// The output chosen by the compositor.
+    Default,
+    /// The output holding the focused window.
+    Focused,
+    /// The output under the cursor, where the compositor exposes it.
+    Cursor,
+    /// The output with the given name, such as `DP-1`.
+    Named(String),
+}
+
+impl From<String> for WindowOutput {
+    fn from(output: String) -> Self {
+        match output.as_str() {
+            "default" => WindowOutput::Default,
+            "focused" => WindowOutput::Focused,
+            "cursor" => WindowOutput::Cursor,
+            _ => WindowOutput::Named(output),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowAnchor {
+    Center,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowLayer {
+    Background,
+    Bottom,
+    Top,
+    Overlay,
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowKeyboardInteractivity {
+    None,
+    Exclusive,
+    OnDemand,
+}
+
 impl Global for Config {}
 
 impl Config {
+    /// Loads the user's config, falling back to the defaults for any broken section.
     pub fn load() -> Self {
-        match std::fs::read_to_string(&*CONFIG_SAVE_PATH) {
-            Ok(file) => toml::from_str(&file).expect("Failed to parse the config file"),
-            Err(_) => Self::default(),
+        let Ok(file) = std::fs::read_to_string(&*CONFIG_SAVE_PATH) else {
+            return Self::default();
+        };
+
+        let (config, errors) = Self::parse(&file);
+        for error in errors {
+            eprintln!("{}:{}", CONFIG_SAVE_PATH.to_string_lossy(), error);
         }
+        config
     }
+
+    /// Reloads the config global whenever the config file changes.
+    pub fn watch(cx: &mut App) {
+        let Some(dir) = CONFIG_SAVE_PATH.parent() else {
+            return;
+        };
+
+        // The directory is watched rather than the file, as editors often replace it on save
+        std::fs::create_dir_all(dir).ok();
+        let changes = match watcher::watch(&[dir.to_path_buf()]) {
+            Ok(changes) => changes,
+            Err(err) => {
+                eprintln!("Failed to watch the config file: {}", err);
+                return;
+            }
+        };
+
+        cx.spawn(async move |cx| {
+            while let Ok(path) = changes.recv().await {
+                if path == *CONFIG_SAVE_PATH {
+                    cx.update(|cx| {
+                        cx.set_global(Config::load());
+                        cx.refresh_windows();
+                        events::emit(Event::ConfigReloaded, cx);
+                    });
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// Validates the config file at `path` and prints a report of its errors.
+    ///
+    /// Returns `true` if the file is valid.
+    pub fn check(path: Option<&Path>) -> bool {
+        let path = path.unwrap_or(CONFIG_SAVE_PATH.as_path());
+        let file = match std::fs::read_to_string(path) {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("Failed to read {}: {}", path.to_string_lossy(), err);
+                return false;
+            }
+        };
+
+        let (_, errors) = Self::parse(&file);
+        if errors.is_empty() {
+            println!("{} is valid", path.to_string_lossy());
+            return true;
+        }
+
+        for error in &errors {
+            eprintln!("{}:{}", path.to_string_lossy(), error);
+        }
+        eprintln!(
+            "{} error(s) found, the affected sections will use their default values",
+            errors.len()
+        );
+        false
+    }
+
+    /// Parses a config file, section by section.
+    ///
+    /// A section that fails to parse is replaced by its default value and reported in the
+    /// returned errors, so a single typo doesn't discard the whole config.
+    pub fn parse(source: &str) -> (Self, Vec<ConfigError>) {
+        let mut errors = Vec::new();
+
+        // Syntax errors make every section unreadable
+        if let Err(err) = source.parse::<toml::Table>() {
+            errors.push(ConfigError::new(source, &err));
+            return (Self::default(), errors);
+        }
+
+        let TerminalSection { terminal } = parse_section(source, &mut errors);
+        let ThemeSection { theme } = parse_section(source, &mut errors);
+        let WindowSection { window } = parse_section(source, &mut errors);
+
+        (
+            Self {
+                terminal,
+                theme,
+                window,
+            },
+            errors,
+        )
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct TerminalSection {
+    terminal: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ThemeSection {
+    theme: ThemeConfig,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct WindowSection {
+    window: WindowConfig,
+}
+
+fn parse_section<T>(source: &str, errors: &mut Vec<ConfigError>) -> T
+where
+    T: DeserializeOwned + Default,
+{
+    toml::from_str(source).unwrap_or_else(|err| {
+        errors.push(ConfigError::new(source, &err));
+        T::default()
+    })
+}
+
+/// An error found in a config file.
+pub struct ConfigError {
+    /// The line and column of the error, both starting at 1.
+    pub position: Option<(usize, usize)>,
+    /// The dotted path of the key holding the invalid value.
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl ConfigError {
+    fn new(source: &str, err: &toml::de::Error) -> Self {
+        let offset = err.span().map(|span| span.start.min(source.len()));
+
+        Self {
+            position: offset.map(|offset| {
+                let before = &source[..offset];
+                let line = before.matches('\n').count() + 1;
+                let column = before
+                    .rsplit_once('\n')
+                    .map_or(before, |(_, line)| line)
+                    .chars()
+                    .count()
+                    + 1;
+                (line, column)
+            }),
+            key: offset.and_then(|offset| key_at(source, offset)),
+            message: err.message().trim().to_string(),
+        }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some((line, column)) = self.position {
+            write!(f, "{}:{}:", line, column)?;
+        }
+        if let Some(ref key) = self.key {
+            write!(f, " `{}`:", key)?;
+        }
+        write!(f, " {}", self.message)
+    }
+}
+
+/// Finds the key defined on the line at `offset`, prefixed by the table it belongs to.
+fn key_at(source: &str, offset: usize) -> Option<String> {
+    let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = source[line_start..].lines().next().unwrap_or_default();
+    let key = line.split_once('=').map(|(key, _)| key.trim())?;
+
+    let table = source[..line_start].lines().rev().find_map(|line| {
+        let line = line.trim();
+        (line.starts_with('[') && !line.starts_with("[["))
+            .then(|| line.trim_matches(['[', ']']).trim())
+    });
+
+    Some(match table {
+        Some(table) => format!("{}.{}", table, key),
+        None => key.to_string(),
+    })
 }
 
-static CONFIG_SAVE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
-    dirs::data_dir()
+pub(crate) static CONFIG_SAVE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    dirs::config_dir()
         .expect("Failed to get config directory")
-        .join("waystart.toml")
+        .join("waystart")
+        .join("config.toml")
 });
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, WindowConfig, key_at};
+
+    #[test]
+    fn keeps_the_valid_sections() {
+        let source = "terminal = \"foot\"\n\n[window]\nheight = 10\nwidth = \"wide\"\n";
+        let (config, errors) = Config::parse(source);
+
+        assert_eq!(config.terminal.as_deref(), Some("foot"));
+        assert!(config.window == WindowConfig::default());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].position, Some((5, 9)));
+        assert_eq!(errors[0].key.as_deref(), Some("window.width"));
+    }
+
+    #[test]
+    fn reports_syntax_errors_once() {
+        let (config, errors) = Config::parse("terminal = \"foot\"\n[window\n");
+
+        assert!(config.terminal.is_none());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].position, Some((2, 8)));
+        assert!(errors[0].key.is_none());
+    }
+
+    #[test]
+    fn finds_the_key_path() {
+        let source = "terminal = 3\n[theme]\n\n[window]\nwidth = 1\n";
+
+        assert_eq!(key_at(source, 0).as_deref(), Some("terminal"));
+        assert_eq!(
+            key_at(source, source.find('1').unwrap()).as_deref(),
+            Some("window.width")
+        );
+        assert_eq!(key_at(source, source.find("[theme]").unwrap()), None);
+    }
+}
@@ -0,0 +1,312 @@
+use gpui::App;
+use zbus::fdo::{self, RequestNameFlags};
+use zbus::interface;
+use zbus::object_server::SignalEmitter;
+
+use crate::ipc::events::Subscribers;
+use crate::ipc::protocol::{Event, OpenOptions, QueryOptions, ResponseBody, SocketMessage};
+use crate::ipc::server::SocketServer;
+
+pub const BUS_NAME: &str = "io.github.waystart";
+const OBJECT_PATH: &str = "/io/github/waystart";
+
+/// A method call forwarded to the app, along with the channel to reply on.
+type Call = (
+    SocketMessage,
+    smol::channel::Sender<Result<ResponseBody, String>>,
+);
+
+/// Owns [`BUS_NAME`] on the session bus, forwarding method calls to `server` and events to signals.
+/// The name is shared by every display, so only the first daemon on the session bus serves it.
+pub fn serve(server: SocketServer, cx: &mut App) {
+    let (calls, requests) = smol::channel::unbounded::<Call>();
+    let events = cx.default_global::<Subscribers>().subscribe();
+
+    cx.spawn(async move |cx| {
+        let builder = match zbus::connection::Builder::session() {
+            Ok(builder) => builder,
+            Err(e) => {
+                eprintln!("Failed to connect to the session bus: {}", e);
+                return;
+            }
+        };
+        let connection = match connect(builder, Service { calls }).await {
+            Ok(connection) => connection,
+            Err(zbus::Error::NameTaken) => {
+                eprintln!(
+                    "Another daemon already owns {} on the session bus, D-Bus is disabled for this one",
+                    BUS_NAME
+                );
+                return;
+            }
+            Err(e) => {
+                eprintln!("Failed to register the D-Bus service: {}", e);
+                return;
+            }
+        };
+
+        let emitter = SignalEmitter::new(&connection, OBJECT_PATH)
+            .expect("Failed to create the D-Bus signal emitter")
+            .into_owned();
+        cx.background_executor()
+            .spawn(async move {
+                while let Ok(event) = events.recv().await {
+                    if let Err(e) = emit_signal(&emitter, event).await {
+                        eprintln!("Failed to emit D-Bus signal: {}", e);
+                    }
+                }
+            })
+            .detach();
+
+        while let Ok((message, reply)) = requests.recv().await {
+            let response = server.handle(message, cx).await;
+            reply.send(response).await.ok();
+        }
+    })
+    .detach();
+}
+
+/// Serves `service` on the bus of `builder` and requests [`BUS_NAME`] without queuing,
+/// failing with [`zbus::Error::NameTaken`] when another daemon owns it.
+async fn connect(
+    builder: zbus::connection::Builder<'_>,
+    service: Service,
+) -> zbus::Result<zbus::Connection> {
+    let connection = builder.serve_at(OBJECT_PATH, service)?.build().await?;
+    connection
+        .request_name_with_flags(BUS_NAME, RequestNameFlags::DoNotQueue.into())
+        .await?;
+    Ok(connection)
+}
+
+async fn emit_signal(emitter: &SignalEmitter<'_>, event: Event) -> zbus::Result<()> {
+    match event {
+        Event::WindowOpened => Service::window_opened(emitter).await,
+        Event::WindowClosed => Service::window_closed(emitter).await,
+        Event::QueryChanged { query } => Service::query_changed(emitter, &query).await,
+        Event::EntryExecuted { entry, finder } => {
+            Service::entry_executed(emitter, &entry, &finder).await
+        }
+        Event::FavoriteToggled { entry, favorite } => {
+            Service::favorite_toggled(emitter, &entry, favorite).await
+        }
+        Event::ConfigReloaded => Service::config_reloaded(emitter).await,
+    }
+}
+
+struct Service {
+    calls: smol::channel::Sender<Call>,
+}
+
+impl Service {
+    async fn call(&self, message: SocketMessage) -> fdo::Result<ResponseBody> {
+        let (sender, receiver) = smol::channel::bounded(1);
+        self.calls
+            .send((message, sender))
+            .await
+            .map_err(|_| fdo::Error::Failed("The daemon is shutting down".to_string()))?;
+        receiver
+            .recv()
+            .await
+            .map_err(|_| fdo::Error::Failed("The daemon is shutting down".to_string()))?
+            .map_err(fdo::Error::Failed)
+    }
+}
+
+/// D-Bus has no optional arguments, so empty strings stand for unset options.
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+#[interface(name = "io.github.waystart.Waystart1")]
+impl Service {
+    /// Opens the window, on `output` with `query` prefilled and only results of `mode`.
+    async fn open(&self, output: &str, query: &str, mode: &str) -> fdo::Result<()> {
+        self.call(SocketMessage::Open(OpenOptions {
+            output: non_empty(output),
+            query: non_empty(query),
+            mode: non_empty(mode),
+        }))
+        .await?;
+        Ok(())
+    }
+
+    async fn close(&self) -> fdo::Result<()> {
+        self.call(SocketMessage::Close).await?;
+        Ok(())
+    }
+
+    /// Opens or closes the window, with the same arguments as `Open`.
+    async fn toggle(&self, output: &str, query: &str, mode: &str) -> fdo::Result<()> {
+        self.call(SocketMessage::Toggle(OpenOptions {
+            output: non_empty(output),
+            query: non_empty(query),
+            mode: non_empty(mode),
+        }))
+        .await?;
+        Ok(())
+    }
+
+    /// Searches without opening the window, returning the id, text, description, score and finder of each result.
+    /// A `limit` of 0 returns every result.
+    async fn query(
+        &self,
+        search_term: &str,
+        mode: &str,
+        limit: u32,
+    ) -> fdo::Result<Vec<(String, String, String, u32, String)>> {
+        let message = SocketMessage::Query(QueryOptions {
+            search_term: search_term.to_string(),
+            mode: non_empty(mode),
+            limit: (limit > 0).then_some(limit as usize),
+        });
+
+        match self.call(message).await? {
+            ResponseBody::Results { results } => Ok(results
+                .into_iter()
+                .map(|result| {
+                    (
+                        result.id,
+                        result.text,
+                        result.description.unwrap_or_default(),
+                        result.score,
+                        result.finder,
+                    )
+                })
+                .collect()),
+            _ => Err(fdo::Error::Failed("Unexpected reply".to_string())),
+        }
+    }
+
+    /// Rescans the applications and reloads the config and favorites.
+    /// Returns the ids of the added and removed entries, and if the config and favorites changed.
+    async fn reload(&self) -> fdo::Result<(Vec<String>, Vec<String>, bool, bool)> {
+        match self.call(SocketMessage::Reload).await? {
+            ResponseBody::Reloaded(report) => Ok((
+                report.added,
+                report.removed,
+                report.config_changed,
+                report.favorites_changed,
+            )),
+            _ => Err(fdo::Error::Failed("Unexpected reply".to_string())),
+        }
+    }
+
+    #[zbus(signal)]
+    async fn window_opened(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn window_closed(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn query_changed(emitter: &SignalEmitter<'_>, query: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn entry_executed(
+        emitter: &SignalEmitter<'_>,
+        entry: &str,
+        finder: &str,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn favorite_toggled(
+        emitter: &SignalEmitter<'_>,
+        entry: &str,
+        favorite: bool,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn config_reloaded(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+
+    use super::{BUS_NAME, Call, OBJECT_PATH, Service, connect};
+    use crate::ipc::protocol::{OpenOptions, ResponseBody, SocketMessage};
+
+    /// A private bus, killed when dropped.
+    struct Bus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl Bus {
+        fn start() -> Self {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .expect("Failed to start dbus-daemon");
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take().unwrap())
+                .read_line(&mut address)
+                .unwrap();
+            Self {
+                daemon,
+                address: address.trim().to_string(),
+            }
+        }
+
+        fn builder(&self) -> zbus::connection::Builder<'_> {
+            zbus::connection::Builder::address(self.address.as_str()).unwrap()
+        }
+    }
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            self.daemon.kill().ok();
+        }
+    }
+
+    fn service() -> (Service, smol::channel::Receiver<Call>) {
+        let (calls, requests) = smol::channel::unbounded();
+        (Service { calls }, requests)
+    }
+
+    #[test]
+    fn forwards_method_calls() {
+        let bus = Bus::start();
+        smol::block_on(async {
+            let (service, requests) = service();
+            let _connection = connect(bus.builder(), service).await.unwrap();
+
+            let client = bus.builder().build().await.unwrap();
+            let call = client.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some("io.github.waystart.Waystart1"),
+                "Open",
+                &("", "fire", ""),
+            );
+            let answer = async {
+                let (message, reply) = requests.recv().await.unwrap();
+                assert!(matches!(
+                    message,
+                    SocketMessage::Open(OpenOptions {
+                        output: None,
+                        query: Some(ref query),
+                        mode: None,
+                    }) if query == "fire"
+                ));
+                reply.send(Ok(ResponseBody::Ok)).await.unwrap();
+            };
+
+            let (result, ()) = smol::future::zip(call, answer).await;
+            result.unwrap();
+        });
+    }
+
+    #[test]
+    fn leaves_the_name_to_the_first_daemon() {
+        let bus = Bus::start();
+        smol::block_on(async {
+            let _first = connect(bus.builder(), service().0).await.unwrap();
+
+            let second = connect(bus.builder(), service().0).await;
+            assert!(matches!(second, Err(zbus::Error::NameTaken)));
+        });
+    }
+}
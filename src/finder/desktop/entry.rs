@@ -1,36 +1,85 @@
-use std::cell::Cell;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::rc::Rc;
 
-use gpui::{App, Resource, SharedString, Window};
+use gpui::{App, ClipboardItem, Resource, SharedString, Window};
 use nucleo_matcher::Utf32String;
+use nucleo_matcher::pattern::Pattern;
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::config::Config;
-use crate::finder::Entry;
 use crate::finder::desktop::create_terminal_command;
 use crate::finder::desktop::frequency::DESKTOP_FREQUENCIES;
+use crate::finder::desktop::index::{self, IndexedEntry};
+use crate::finder::saves::Saves;
+use crate::finder::search::{Match, highlight_ranges};
+use crate::finder::{Entry, Highlights, desktop};
+use crate::ui::elements::DropdownItem;
 
 pub struct DesktopEntry {
     pub id: SharedString,
     pub name: SharedString,
     pub description: Option<SharedString>,
-    pub icon: Option<Resource>,
-    pub haystack: nucleo_matcher::Utf32String,
-    score: Cell<u32>,
+    /// The id of the application this entry is a desktop action of.
+    pub parent: Option<SharedString>,
+    /// Fields that aren't shown, only searched.
+    generic_name: Option<String>,
+    keywords: Vec<String>,
+    categories: Vec<String>,
+    executable: Option<String>,
+    /// The icon name, looked up the first time the icon is shown.
+    icon_name: Option<String>,
+    icon: OnceCell<Option<Resource>>,
     exec: Vec<String>,
     working_dir: Option<PathBuf>,
     open_in_terminal: bool,
+    path: PathBuf,
 }
 
-impl Entry for DesktopEntry {
+/// A desktop entry as returned by one search, with its own score and highlights, so searches
+/// running at the same time, such as IPC queries, don't overwrite each other's.
+pub struct DesktopResult {
+    entry: Rc<DesktopEntry>,
+    score: u32,
+    highlights: Highlights,
+}
+
+impl DesktopResult {
+    /// Wraps `entry`, highlighting the characters of its haystack at the given sorted positions.
+    pub fn new(entry: Rc<DesktopEntry>, score: u32, indices: &[u32]) -> Self {
+        let highlights = entry.highlights_at(indices);
+        Self {
+            entry,
+            score,
+            highlights,
+        }
+    }
+}
+
+impl Deref for DesktopResult {
+    type Target = DesktopEntry;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entry
+    }
+}
+
+impl Entry for DesktopResult {
     fn id(&self) -> SharedString {
         self.id.clone()
     }
 
+    fn finder(&self) -> &'static str {
+        desktop::NAME
+    }
+
     fn score(&self) -> u32 {
-        self.score.get()
+        self.score
     }
 
     fn text(&self) -> SharedString {
@@ -42,18 +91,75 @@ impl Entry for DesktopEntry {
     }
 
     fn icon(&self) -> Option<Resource> {
-        self.icon.clone()
+        self.icon
+            .get_or_init(|| self.icon_name.as_deref().and_then(lookup_icon))
+            .clone()
+    }
+
+    fn highlights(&self) -> Highlights {
+        self.highlights.clone()
     }
 
     fn can_favorite(&self) -> bool {
         true
     }
 
+    fn actions(&self) -> Vec<DropdownItem> {
+        let mut actions = Vec::new();
+
+        if !self.open_in_terminal {
+            let name = self.name.clone();
+            let exec = self.exec.clone();
+            let working_dir = self.working_dir.clone();
+            actions.push(DropdownItem::new(
+                "action-terminal",
+                "Launch in terminal",
+                None,
+                move |window, cx| {
+                    let cmd = create_terminal_command(cx.global::<Config>(), &exec);
+                    spawn(&name, cmd, working_dir.as_deref(), window);
+                },
+            ));
+        }
+
+        let path = self.path.clone();
+        actions.push(DropdownItem::new(
+            "action-open-desktop-file",
+            "Open containing .desktop file",
+            None,
+            move |window, _| {
+                let mut cmd = Command::new("xdg-open");
+                cmd.arg(&path);
+                spawn(&path.to_string_lossy(), cmd, None, window);
+            },
+        ));
+
+        let exec = self
+            .exec
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        actions.push(DropdownItem::new(
+            "action-copy-exec",
+            "Copy exec line",
+            None,
+            move |window, cx| {
+                cx.write_to_clipboard(ClipboardItem::new_string(exec.clone()));
+                window.remove_window();
+            },
+        ));
+
+        actions
+    }
+
     fn execute(&self, window: &mut Window, cx: &mut App) {
         DESKTOP_FREQUENCIES.increment_frequency(&self.id);
+        // Persist right away so a long-running daemon doesn't lose its history
+        Saves::spawn(DESKTOP_FREQUENCIES.save(), cx);
 
         let config = cx.global::<Config>();
-        let mut cmd = if self.open_in_terminal {
+        let cmd = if self.open_in_terminal {
             create_terminal_command(config, &self.exec)
         } else {
             let [exec, args @ ..] = self.exec.as_slice() else {
@@ -66,82 +172,461 @@ impl Entry for DesktopEntry {
             cmd
         };
 
-        if let Some(ref cwd) = self.working_dir {
-            cmd.current_dir(cwd);
-        } else if let Some(cwd) = std::env::home_dir() {
-            cmd.current_dir(cwd);
-        }
-
-        cmd.stdout(Stdio::null()).stderr(Stdio::null());
-        match cmd.spawn() {
-            Ok(_) => window.remove_window(),
-            Err(e) => {
-                eprintln!("Failed to launch {}: {}.", self.name, e);
-            }
-        }
+        spawn(&self.name, cmd, self.working_dir.as_deref(), window);
     }
 }
 
 impl DesktopEntry {
+    /// Loads every application, from the index when the application directories didn't change.
     pub fn load() -> Vec<Rc<DesktopEntry>> {
-        let paths = freedesktop_desktop_entry::default_paths();
+        let dirs = freedesktop_desktop_entry::default_paths().collect::<Vec<_>>();
         let locales = freedesktop_desktop_entry::get_languages_from_env();
 
-        let mut entries = HashMap::<SharedString, DesktopEntry>::new();
-        for entry in freedesktop_desktop_entry::Iter::new(paths).entries(Some(&locales)) {
-            if entry.no_display() || entry.hidden() {
+        index::load_or_parse(&dirs, &locales, || Self::parse(&dirs, &locales))
+            .into_iter()
+            .map(|entry| Rc::new(DesktopEntry::from(entry)))
+            .collect()
+    }
+
+    fn parse(dirs: &[PathBuf], locales: &[String]) -> Vec<IndexedEntry> {
+        let mut entries = Vec::new();
+        // The first file with an id shadows the others, even when it hides the application
+        let mut seen = HashSet::new();
+        for path in index::desktop_files(dirs) {
+            let Some(id) = path.file_stem() else {
+                continue;
+            };
+            if !seen.insert(id.to_os_string()) {
                 continue;
             }
 
-            let id = SharedString::from(entry.id().to_string());
-            if entries.contains_key(&id) {
-                continue;
+            entries.extend(Self::read(&path, locales));
+        }
+
+        entries
+    }
+
+    /// Loads the application with the given id, along with its desktop actions, from the
+    /// desktop file that takes precedence.
+    pub fn load_id(id: &str) -> Vec<DesktopEntry> {
+        let dirs = freedesktop_desktop_entry::default_paths().collect::<Vec<_>>();
+        let locales = freedesktop_desktop_entry::get_languages_from_env();
+
+        // Files found later are shadowed, even when this one hides the application
+        let Some(path) = index::desktop_files(&dirs)
+            .into_iter()
+            .find(|path| path.file_stem() == Some(OsStr::new(id)))
+        else {
+            return Vec::new();
+        };
+
+        Self::read(&path, &locales)
+            .into_iter()
+            .map(DesktopEntry::from)
+            .collect()
+    }
+
+    /// Reads the entries of the desktop file at `path`, reporting it when it can't be parsed.
+    fn read(path: &Path, locales: &[String]) -> Vec<IndexedEntry> {
+        match freedesktop_desktop_entry::DesktopEntry::from_path(path, Some(locales)) {
+            Ok(entry) => Self::from_desktop_file(&entry, locales),
+            Err(err) => {
+                eprintln!("Failed to parse {}: {}", path.to_string_lossy(), err);
+                Vec::new()
             }
+        }
+    }
+
+    /// Reads the entries of a desktop file: the application, followed by its desktop actions.
+    fn from_desktop_file(
+        entry: &freedesktop_desktop_entry::DesktopEntry,
+        locales: &[String],
+    ) -> Vec<IndexedEntry> {
+        if entry.no_display() || entry.hidden() {
+            return Vec::new();
+        }
 
-            let Ok(exec) = entry.parse_exec_with_uris(&[], &locales) else {
+        let id = SharedString::from(entry.id().to_string());
+        let Ok(exec) = entry.parse_exec_with_uris(&[], locales) else {
+            return Vec::new();
+        };
+        let name = match entry.name(locales) {
+            Some(name) => SharedString::from(name.into_owned()),
+            None => return Vec::new(),
+        };
+        let description = entry
+            .comment(locales)
+            .map(|description| SharedString::from(description.into_owned()));
+        let generic_name = entry
+            .generic_name(locales)
+            .map(|generic_name| generic_name.into_owned());
+        let keywords = entry
+            .keywords(locales)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|keyword| keyword.into_owned())
+            .collect();
+        let categories = entry
+            .categories()
+            .unwrap_or_default()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let executable = Self::executable(entry.desktop_entry("TryExec"), &exec);
+        let icon = entry.icon().map(str::to_string);
+        let working_dir = entry.path().and_then(|entry| entry.parse().ok());
+
+        let mut entries = vec![IndexedEntry {
+            id: id.clone(),
+            name: name.clone(),
+            description: description.clone(),
+            generic_name,
+            keywords,
+            categories,
+            executable,
+            icon: icon.clone(),
+            parent: None,
+            exec,
+            working_dir: working_dir.clone(),
+            open_in_terminal: entry.terminal(),
+            path: entry.path.clone(),
+        }];
+
+        for action in entry.actions().unwrap_or_default() {
+            let Ok(exec) = entry.parse_exec_action_with_uris(action, &[], locales) else {
                 continue;
             };
-            let name = match entry.name(&locales) {
-                Some(name) => SharedString::from(name.into_owned()),
-                None => continue,
+            let Some(action_name) = entry.action_name(action, locales) else {
+                continue;
             };
-            let description = entry
-                .comment(&locales)
-                .map(|description| SharedString::from(description.into_owned()));
-            let icon = entry
-                .icon()
-                .and_then(|icon| {
-                    freedesktop_icons::lookup(icon)
-                        .with_cache()
-                        .with_size(28)
-                        .find()
-                })
-                .map(|path| Resource::Path(path.into()));
-            let haystack = Utf32String::from(match description {
-                Some(ref d) => name.to_string() + " " + d.as_str(),
-                None => name.to_string(),
+
+            entries.push(IndexedEntry {
+                id: SharedString::from(format!("{}:{}", id, action)),
+                name: SharedString::from(format!("{}: {}", name, action_name)),
+                description: description.clone(),
+                // Actions are only searched by name, so they don't crowd the results
+                generic_name: None,
+                keywords: Vec::new(),
+                categories: Vec::new(),
+                executable: None,
+                icon: entry
+                    .action_entry(action, "Icon")
+                    .map(str::to_string)
+                    .or_else(|| icon.clone()),
+                parent: Some(id.clone()),
+                exec,
+                working_dir: working_dir.clone(),
+                open_in_terminal: entry.terminal(),
+                path: entry.path.clone(),
             });
+        }
 
-            entries.insert(
-                id.clone(),
-                DesktopEntry {
-                    id,
-                    name,
-                    description,
-                    icon,
-                    haystack,
-                    exec,
-                    score: Cell::new(0),
-                    working_dir: entry.path().and_then(|entry| entry.parse().ok()),
-                    open_in_terminal: entry.terminal(),
-                },
-            );
+        entries
+    }
+
+    /// Returns the highlights of the characters of the haystack at the given sorted positions.
+    fn highlights_at(&self, indices: &[u32]) -> Highlights {
+        // The description follows the name and a space in the haystack
+        let name_len = self.name.graphemes(true).count() as u32;
+        let split = indices.partition_point(|&i| i < name_len);
+        let description = indices[split..]
+            .iter()
+            .filter_map(|&i| i.checked_sub(name_len + 1))
+            .collect::<Vec<_>>();
+
+        Highlights {
+            text: highlight_ranges(&self.name, &indices[..split]),
+            description: match self.description {
+                Some(ref d) if !self.is_action() => highlight_ranges(d, &description),
+                _ => Vec::new(),
+            },
         }
+    }
+
+    /// Returns the fields the search term is matched against.
+    pub fn haystack(&self) -> Haystack {
+        // Actions are only matched on their name, which already holds the application's
+        let text = Utf32String::from(match self.description {
+            Some(ref d) if !self.is_action() => self.name.to_string() + " " + d.as_str(),
+            _ => self.name.to_string(),
+        });
 
-        entries.into_values().map(Rc::new).collect()
+        let fields = [
+            (self.generic_name.clone(), GENERIC_NAME_WEIGHT),
+            (self.executable.clone(), EXECUTABLE_WEIGHT),
+            (Some(self.keywords.join(" ")), KEYWORDS_WEIGHT),
+            (Some(self.categories.join(" ")), CATEGORIES_WEIGHT),
+        ];
+
+        Haystack {
+            text,
+            fields: fields
+                .into_iter()
+                .filter_map(|(field, weight)| Some((field.filter(|f| !f.is_empty())?, weight)))
+                .map(|(field, weight)| (Utf32String::from(field), weight))
+                .collect(),
+        }
+    }
+
+    /// Returns the name of the program launched by an entry, without its directory.
+    ///
+    /// `TryExec` names it best, otherwise launchers such as flatpak, snap and shells are looked
+    /// through for the program they run.
+    fn executable(try_exec: Option<&str>, exec: &[String]) -> Option<String> {
+        if let Some(try_exec) = try_exec {
+            return file_name(try_exec);
+        }
+
+        // Skip `env` and the variables it sets
+        let mut args = exec
+            .iter()
+            .map(String::as_str)
+            .skip_while(|arg| *arg == "env" || arg.contains('='));
+        let program = file_name(args.next()?)?;
+
+        match program.as_str() {
+            // The program is either given by `--command`, or named after the app id
+            "flatpak" | "snap" => args.filter(|arg| *arg != "run").find_map(|arg| {
+                match arg.strip_prefix("--command=") {
+                    Some(command) => file_name(command),
+                    None => (!arg.starts_with('-')).then(|| arg.to_string()),
+                }
+            }),
+            "sh" | "bash" | "dash" | "zsh" => {
+                let script = args.skip_while(|arg| *arg != "-c").nth(1)?;
+                let words = script
+                    .split_whitespace()
+                    .filter(|word| *word != "exec")
+                    .map(str::to_string)
+                    .collect::<Vec<_>>();
+                Self::executable(None, &words)
+            }
+            _ => Some(program),
+        }
+    }
+
+    /// If this entry is a desktop action of another application.
+    pub fn is_action(&self) -> bool {
+        self.parent.is_some()
+    }
+}
+
+/// The weights of the fields that aren't shown, in percent of a match on the name or description.
+///
+/// They rank a match on the name above an equally good match on any other field.
+const GENERIC_NAME_WEIGHT: u32 = 70;
+const EXECUTABLE_WEIGHT: u32 = 70;
+const KEYWORDS_WEIGHT: u32 = 50;
+const CATEGORIES_WEIGHT: u32 = 40;
+
+/// The fields of a desktop entry the search term is matched against.
+pub struct Haystack {
+    /// The name, followed by the description for applications, as shown in the results.
+    text: Utf32String,
+    /// The fields that are only searched, with their weight.
+    fields: Vec<(Utf32String, u32)>,
+}
+
+impl Haystack {
+    /// Matches each word of `pattern` against the field where it scores best, adding up their
+    /// weighted scores, so "code editor" finds an editor named Code.
+    ///
+    /// Only the shown text is highlighted, so the other fields don't record their indices.
+    pub fn matches(
+        &self,
+        index: usize,
+        pattern: &Pattern,
+        matcher: &mut nucleo_matcher::Matcher,
+    ) -> Option<Match> {
+        let mut score = 0;
+        let mut indices = Vec::new();
+
+        for atom in &pattern.atoms {
+            let mut atom_indices = Vec::new();
+            let text = atom
+                .indices(self.text.slice(..), matcher, &mut atom_indices)
+                .map(u32::from);
+            let field = self
+                .fields
+                .iter()
+                .filter_map(|(field, weight)| {
+                    Some(u32::from(atom.score(field.slice(..), matcher)?) * weight / 100)
+                })
+                .max();
+            score += text.max(field)?;
+            if text.is_some() {
+                indices.extend(atom_indices);
+            }
+        }
+
+        indices.sort_unstable();
+        indices.dedup();
+        Some(Match {
+            index,
+            score,
+            indices,
+        })
+    }
+}
+
+impl From<IndexedEntry> for DesktopEntry {
+    fn from(entry: IndexedEntry) -> Self {
+        Self {
+            id: entry.id,
+            name: entry.name,
+            description: entry.description,
+            generic_name: entry.generic_name,
+            keywords: entry.keywords,
+            categories: entry.categories,
+            executable: entry.executable,
+            parent: entry.parent,
+            icon_name: entry.icon,
+            icon: OnceCell::new(),
+            exec: entry.exec,
+            working_dir: entry.working_dir,
+            open_in_terminal: entry.open_in_terminal,
+            path: entry.path,
+        }
+    }
+}
+
+fn spawn(name: &str, mut cmd: Command, working_dir: Option<&Path>, window: &mut Window) {
+    if let Some(cwd) = working_dir {
+        cmd.current_dir(cwd);
+    } else if let Some(cwd) = std::env::home_dir() {
+        cmd.current_dir(cwd);
     }
 
-    pub fn set_score(&self, score: u32) {
-        self.score.replace(score);
+    cmd.stdout(Stdio::null()).stderr(Stdio::null());
+    match cmd.spawn() {
+        Ok(_) => window.remove_window(),
+        Err(e) => {
+            eprintln!("Failed to launch {}: {}.", name, e);
+        }
+    }
+}
+
+/// Quotes `arg` for a shell, unless it is only made of characters that are safe unquoted.
+fn shell_quote(arg: &str) -> Cow<'_, str> {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "%+,-./:=@_".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        Cow::Borrowed(arg)
+    } else {
+        Cow::Owned(format!("'{}'", arg.replace('\'', "'\\''")))
+    }
+}
+
+fn file_name(path: &str) -> Option<String> {
+    Some(Path::new(path).file_name()?.to_str()?.to_string())
+}
+
+fn lookup_icon(icon: &str) -> Option<Resource> {
+    freedesktop_icons::lookup(icon)
+        .with_cache()
+        .with_size(28)
+        .find()
+        .map(|path| Resource::Path(path.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::{DesktopEntry, DesktopResult, shell_quote};
+    use crate::finder::Entry;
+    use crate::finder::desktop::index::IndexedEntry;
+
+    fn executable(try_exec: Option<&str>, exec: &[&str]) -> Option<String> {
+        let exec = exec.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        DesktopEntry::executable(try_exec, &exec)
+    }
+
+    #[test]
+    fn strips_the_directory() {
+        assert_eq!(
+            executable(None, &["/usr/bin/firefox", "%u"]).as_deref(),
+            Some("firefox")
+        );
+    }
+
+    #[test]
+    fn prefers_try_exec() {
+        assert_eq!(
+            executable(Some("/usr/bin/code"), &["/usr/share/code/launch"]).as_deref(),
+            Some("code")
+        );
+    }
+
+    #[test]
+    fn skips_env() {
+        assert_eq!(
+            executable(None, &["env", "GDK_BACKEND=x11", "LANG=C", "steam"]).as_deref(),
+            Some("steam")
+        );
+    }
+
+    #[test]
+    fn skips_flatpak_and_snap() {
+        let flatpak = [
+            "/usr/bin/flatpak",
+            "run",
+            "--branch=stable",
+            "--command=code",
+            "com.visualstudio.code",
+        ];
+        assert_eq!(executable(None, &flatpak).as_deref(), Some("code"));
+        assert_eq!(
+            executable(None, &["flatpak", "run", "org.gimp.GIMP", "%U"]).as_deref(),
+            Some("org.gimp.GIMP")
+        );
+        assert_eq!(
+            executable(None, &["snap", "run", "spotify"]).as_deref(),
+            Some("spotify")
+        );
+    }
+
+    #[test]
+    fn skips_shells() {
+        assert_eq!(
+            executable(None, &["sh", "-c", "exec /opt/app/bin/app --flag"]).as_deref(),
+            Some("app")
+        );
+        assert_eq!(executable(None, &["bash", "script.sh"]), None);
+    }
+
+    #[test]
+    fn quotes_arguments_for_the_shell() {
+        assert_eq!(shell_quote("--new-window"), "--new-window");
+        assert_eq!(shell_quote("/opt/My App/run"), "'/opt/My App/run'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn keeps_the_highlights_of_each_search() {
+        let entry = Rc::new(DesktopEntry::from(IndexedEntry {
+            id: "firefox".into(),
+            name: "Firefox".into(),
+            description: Some("Web Browser".into()),
+            generic_name: None,
+            keywords: Vec::new(),
+            categories: Vec::new(),
+            executable: None,
+            icon: None,
+            parent: None,
+            exec: vec!["firefox".to_string()],
+            working_dir: None,
+            open_in_terminal: false,
+            path: "firefox.desktop".into(),
+        }));
+
+        let name = DesktopResult::new(entry.clone(), 100, &[0, 1]);
+        let description = DesktopResult::new(entry, 50, &[8, 9, 10]);
+
+        assert_eq!(name.highlights().text, [0..2]);
+        assert!(name.highlights().description.is_empty());
+        assert!(description.highlights().text.is_empty());
+        assert_eq!(description.highlights().description, [0..3]);
+        assert_eq!((name.score(), description.score()), (100, 50));
     }
 }
@@ -8,42 +8,121 @@ use std::time::SystemTime;
 use gpui::SharedString;
 use serde::{Deserialize, Serialize};
 
+use crate::finder::saves;
+
 pub static DESKTOP_FREQUENCIES: LazyLock<Frequencies> = LazyLock::new(Frequencies::load);
 
-pub struct Frequencies(Mutex<HashMap<SharedString, EntryFrequency>>);
+pub struct Frequencies {
+    entries: Mutex<HashMap<SharedString, EntryFrequency>>,
+    /// How often each entry was chosen after typing each query prefix.
+    queries: Mutex<HashMap<String, HashMap<SharedString, EntryFrequency>>>,
+}
 
 impl Frequencies {
     pub fn load() -> Self {
-        Self(Mutex::new(
-            match std::fs::read_to_string(&*FREQUENCIES_SAVE_PATH) {
-                Ok(file) => toml::from_str(&file).expect("Failed to parse frequency history"),
-                Err(_) => HashMap::new(),
-            },
-        ))
+        Self {
+            entries: Mutex::new(saves::load(&FREQUENCIES_SAVE_PATH, "frequency history")),
+            queries: Mutex::new(saves::load(&QUERIES_SAVE_PATH, "query history")),
+        }
     }
 
     pub async fn save(&self) {
-        let content = toml::to_string(&self.0).expect("Failed to serialize frequency history");
-        if let Err(err) = smol::fs::write(&*FREQUENCIES_SAVE_PATH, content).await {
-            eprintln!(
-                "Failed to save frequency history at {}: {}",
-                FREQUENCIES_SAVE_PATH.to_string_lossy(),
-                err
-            );
+        let content =
+            toml::to_string(&self.entries).expect("Failed to serialize frequency history");
+        let queries = {
+            let mut queries = self.queries.lock().unwrap();
+            // Forget the choices that decayed completely
+            queries.retain(|_, entries| {
+                entries.retain(|_, frequency| frequency.score() > 0);
+                !entries.is_empty()
+            });
+            toml::to_string(&*queries).expect("Failed to serialize query history")
+        };
+
+        if let Some(dir) = FREQUENCIES_SAVE_PATH.parent() {
+            smol::fs::create_dir_all(dir).await.ok();
+        }
+        for (name, path, content) in [
+            ("frequency history", &*FREQUENCIES_SAVE_PATH, content),
+            ("query history", &*QUERIES_SAVE_PATH, queries),
+        ] {
+            if let Err(err) = smol::fs::write(path, content).await {
+                eprintln!(
+                    "Failed to save {} at {}: {}",
+                    name,
+                    path.to_string_lossy(),
+                    err
+                );
+            }
         }
     }
 
     pub fn increment_frequency(&self, entry_id: &SharedString) {
-        let mut guard = self.0.lock().unwrap();
-        if let Some(frequency) = guard.get_mut(entry_id) {
-            frequency.increment();
-        } else {
-            guard.insert(entry_id.clone(), EntryFrequency::new());
+        increment(&mut self.entries.lock().unwrap(), entry_id);
+    }
+
+    /// Remembers that the entry was chosen after typing `query`, for each of its prefixes.
+    pub fn increment_query_frequency(&self, query: &str, entry_id: &SharedString) {
+        let mut queries = self.queries.lock().unwrap();
+        for prefix in prefixes(&normalize(query)) {
+            increment(queries.entry(prefix.to_string()).or_default(), entry_id);
         }
     }
 
     pub fn score(&self, id: &SharedString) -> u32 {
-        self.0.lock().unwrap().get(id).map_or(0, |freq| freq.score)
+        self.entries
+            .lock()
+            .unwrap()
+            .get(id)
+            .map_or(0, EntryFrequency::score)
+    }
+
+    /// Returns the scores of the entries chosen after typing `query`.
+    ///
+    /// Queries longer than those remembered use the scores of their longest remembered prefix.
+    pub fn query_scores(&self, query: &str) -> HashMap<SharedString, u32> {
+        let queries = self.queries.lock().unwrap();
+        let query = normalize(query);
+
+        prefixes(&query)
+            .into_iter()
+            .rev()
+            .find_map(|prefix| queries.get(prefix))
+            .map(|entries| {
+                entries
+                    .iter()
+                    .map(|(id, frequency)| (id.clone(), frequency.score()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Longer queries are remembered by their prefix of this many characters.
+const MAX_QUERY_LEN: usize = 16;
+
+fn normalize(query: &str) -> String {
+    query.trim().to_lowercase()
+}
+
+/// Returns the non-empty prefixes of `query`, from the shortest to the longest remembered.
+fn prefixes(query: &str) -> Vec<&str> {
+    query
+        .char_indices()
+        .skip(1)
+        .map(|(i, _)| i)
+        .chain([query.len()])
+        .filter(|&end| end > 0)
+        .take(MAX_QUERY_LEN)
+        .map(|end| &query[..end])
+        .collect()
+}
+
+fn increment(frequencies: &mut HashMap<SharedString, EntryFrequency>, entry_id: &SharedString) {
+    if let Some(frequency) = frequencies.get_mut(entry_id) {
+        frequency.increment();
+    } else {
+        frequencies.insert(entry_id.clone(), EntryFrequency::new());
     }
 }
 
@@ -116,8 +195,48 @@ impl PartialEq for EntryFrequency {
     }
 }
 
-static FREQUENCIES_SAVE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
-    dirs::cache_dir()
-        .expect("Failed to get cache directory")
-        .join("waystart.toml")
+pub(crate) static FREQUENCIES_SAVE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    dirs::state_dir()
+        .expect("Failed to get state directory")
+        .join("waystart")
+        .join("history.toml")
 });
+
+pub(crate) static QUERIES_SAVE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    dirs::state_dir()
+        .expect("Failed to get state directory")
+        .join("waystart")
+        .join("queries.toml")
+});
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_QUERY_LEN, normalize, prefixes};
+
+    #[test]
+    fn lists_prefixes_from_the_shortest() {
+        assert_eq!(prefixes("fire"), ["f", "fi", "fir", "fire"]);
+        assert!(prefixes("").is_empty());
+    }
+
+    #[test]
+    fn splits_on_characters() {
+        assert_eq!(prefixes("é😀a"), ["é", "é😀", "é😀a"]);
+    }
+
+    #[test]
+    fn remembers_long_queries_by_their_prefix() {
+        let query = "a".repeat(MAX_QUERY_LEN + 4);
+        let prefixes = prefixes(&query);
+        assert_eq!(prefixes.len(), MAX_QUERY_LEN);
+        assert_eq!(
+            prefixes.last().map(|prefix| prefix.len()),
+            Some(MAX_QUERY_LEN)
+        );
+    }
+
+    #[test]
+    fn normalizes_case_and_whitespace() {
+        assert_eq!(normalize("  FireFox "), "firefox");
+    }
+}
@@ -0,0 +1,260 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use std::time::SystemTime;
+
+use gpui::SharedString;
+use serde::{Deserialize, Serialize};
+
+/// The version of the index, to bump whenever the entries are parsed or stored differently, as
+/// the files alone don't tell an index written by another version apart.
+const INDEX_VERSION: u32 = 2;
+
+/// The desktop entries parsed on a previous launch, reused until an application directory or
+/// desktop file changes.
+#[derive(Serialize, Deserialize)]
+struct DesktopIndex {
+    version: u32,
+    dirs: Vec<DirState>,
+    locales: Vec<String>,
+    entries: Vec<IndexedEntry>,
+}
+
+/// A desktop entry as stored in the index, before its icon is resolved.
+#[derive(Serialize, Deserialize)]
+pub struct IndexedEntry {
+    pub id: SharedString,
+    pub name: SharedString,
+    pub description: Option<SharedString>,
+    pub generic_name: Option<String>,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    /// The name of the program launched by the entry, searched like the keywords.
+    pub executable: Option<String>,
+    pub icon: Option<String>,
+    pub parent: Option<SharedString>,
+    pub exec: Vec<String>,
+    pub working_dir: Option<PathBuf>,
+    pub open_in_terminal: bool,
+    pub path: PathBuf,
+}
+
+/// The state of an application directory, which changes whenever a file is added, removed or
+/// replaced in it.
+#[derive(PartialEq, Serialize, Deserialize)]
+struct DirState {
+    path: PathBuf,
+    /// Where the directory resolves to, as profiles like nix's swap it without touching its
+    /// content.
+    target: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    /// The desktop files of the directory, which change without it when edited in place.
+    files: Vec<FileState>,
+}
+
+#[derive(PartialEq, Serialize, Deserialize)]
+struct FileState {
+    name: OsString,
+    modified: Option<SystemTime>,
+}
+
+/// Returns the saved entries if `dirs` didn't change since they were indexed by this version in
+/// the given locales, or indexes the entries returned by `parse` otherwise.
+pub fn load_or_parse(
+    dirs: &[PathBuf],
+    locales: &[String],
+    parse: impl FnOnce() -> Vec<IndexedEntry>,
+) -> Vec<IndexedEntry> {
+    // The state is taken before parsing, so changes made meanwhile invalidate the new index
+    let states = dir_states(dirs);
+    if let Some(index) = load()
+        && index.version == INDEX_VERSION
+        && index.dirs == states
+        && index.locales == locales
+    {
+        return index.entries;
+    }
+
+    let index = DesktopIndex {
+        version: INDEX_VERSION,
+        dirs: states,
+        locales: locales.to_vec(),
+        entries: parse(),
+    };
+    save(&index);
+    index.entries
+}
+
+/// Discards the saved index, so the entries are parsed again on the next load.
+pub fn invalidate() {
+    if let Err(err) = std::fs::remove_file(&*DESKTOP_INDEX_SAVE_PATH)
+        && err.kind() != std::io::ErrorKind::NotFound
+    {
+        eprintln!(
+            "Failed to remove desktop index at {}: {}",
+            DESKTOP_INDEX_SAVE_PATH.to_string_lossy(),
+            err
+        );
+    }
+}
+
+fn load() -> Option<DesktopIndex> {
+    let file = std::fs::read(&*DESKTOP_INDEX_SAVE_PATH).ok()?;
+    // An unreadable index, such as one written before the version was stored, is simply rebuilt
+    serde_json::from_slice(&file).ok()
+}
+
+fn save(index: &DesktopIndex) {
+    let content = serde_json::to_vec(index).expect("Failed to serialize desktop index");
+    if let Some(dir) = DESKTOP_INDEX_SAVE_PATH.parent() {
+        std::fs::create_dir_all(dir).ok();
+    }
+    if let Err(err) = std::fs::write(&*DESKTOP_INDEX_SAVE_PATH, content) {
+        eprintln!(
+            "Failed to save desktop index at {}: {}",
+            DESKTOP_INDEX_SAVE_PATH.to_string_lossy(),
+            err
+        );
+    }
+}
+
+/// Returns `dirs` and their subdirectories, where desktop files are looked up.
+pub fn lookup_dirs(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    app_dirs(dirs).into_iter().map(|dir| dir.path).collect()
+}
+
+/// Returns the desktop files of `dirs` and their subdirectories, the ones that take precedence
+/// first.
+pub fn desktop_files(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    app_dirs(dirs)
+        .into_iter()
+        .flat_map(|dir| dir.files)
+        .collect()
+}
+
+/// Returns the state of `dirs` and of their subdirectories, where desktop files are also found.
+fn dir_states(dirs: &[PathBuf]) -> Vec<DirState> {
+    app_dirs(dirs)
+        .into_iter()
+        .map(|dir| {
+            let files = dir
+                .files
+                .iter()
+                .map(|file| FileState {
+                    name: file.file_name().unwrap_or_default().to_os_string(),
+                    // Follows symlinks, as profiles like nix's link every desktop file
+                    modified: std::fs::metadata(file)
+                        .and_then(|metadata| metadata.modified())
+                        .ok(),
+                })
+                .collect();
+
+            DirState {
+                target: std::fs::canonicalize(&dir.path).ok(),
+                modified: dir.modified,
+                files,
+                path: dir.path,
+            }
+        })
+        .collect()
+}
+
+/// An application directory, or one of its subdirectories, along with its desktop files.
+struct AppDir {
+    path: PathBuf,
+    /// Taken before listing the files, so changes made meanwhile show up on the next look.
+    modified: Option<SystemTime>,
+    files: Vec<PathBuf>,
+}
+
+/// Walks `dirs` and their subdirectories depth first, listing the sorted desktop files of each,
+/// so the index, the watcher and the lookup of a single id agree on which file comes first.
+fn app_dirs(dirs: &[PathBuf]) -> Vec<AppDir> {
+    let mut app_dirs = Vec::new();
+    let mut pending = dirs.iter().rev().cloned().collect::<Vec<_>>();
+
+    while let Some(path) = pending.pop() {
+        let modified = std::fs::metadata(&path).and_then(|metadata| metadata.modified());
+        let mut files = Vec::new();
+        if let Ok(dir) = std::fs::read_dir(&path) {
+            let mut subdirs = Vec::new();
+            for entry in dir.filter_map(Result::ok) {
+                let entry_path = entry.path();
+                if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                    subdirs.push(entry_path);
+                } else if entry_path
+                    .extension()
+                    .is_some_and(|extension| extension == "desktop")
+                {
+                    files.push(entry_path);
+                }
+            }
+            subdirs.sort_unstable_by(|a, b| b.cmp(a));
+            pending.extend(subdirs);
+            files.sort_unstable();
+        }
+
+        app_dirs.push(AppDir {
+            path,
+            modified: modified.ok(),
+            files,
+        });
+    }
+
+    app_dirs
+}
+
+pub(crate) static DESKTOP_INDEX_SAVE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    dirs::cache_dir()
+        .expect("Failed to get cache directory")
+        .join("waystart")
+        .join("desktop-index.json")
+});
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{desktop_files, lookup_dirs};
+
+    #[test]
+    fn finds_desktop_files_in_subdirectories() {
+        let dir = tempfile::tempdir().unwrap();
+        let apps = dir.path().join("applications");
+        fs::create_dir_all(apps.join("kde/nested")).unwrap();
+        fs::create_dir_all(apps.join("wine")).unwrap();
+        for file in [
+            "zed.desktop",
+            "firefox.desktop",
+            "notes.txt",
+            "kde/nested/okular.desktop",
+            "kde/dolphin.desktop",
+            "wine/notepad.desktop",
+        ] {
+            fs::write(apps.join(file), "").unwrap();
+        }
+        let missing = dir.path().join("missing");
+        let dirs = [apps.clone(), missing.clone()];
+
+        assert_eq!(
+            desktop_files(&dirs),
+            [
+                apps.join("firefox.desktop"),
+                apps.join("zed.desktop"),
+                apps.join("kde/dolphin.desktop"),
+                apps.join("kde/nested/okular.desktop"),
+                apps.join("wine/notepad.desktop"),
+            ]
+        );
+        assert_eq!(
+            lookup_dirs(&dirs),
+            [
+                apps.clone(),
+                apps.join("kde"),
+                apps.join("kde/nested"),
+                apps.join("wine"),
+                missing,
+            ]
+        );
+    }
+}
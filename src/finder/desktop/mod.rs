@@ -1,65 +1,131 @@
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::Arc;
 
+use gpui::SharedString;
 use nucleo_matcher::pattern::{AtomKind, CaseMatching, Normalization, Pattern};
 
-use crate::finder::desktop::entry::DesktopEntry;
-use crate::finder::desktop::frequency::Frequencies;
+use crate::finder::desktop::entry::{DesktopEntry, DesktopResult, Haystack};
+use crate::finder::desktop::frequency::DESKTOP_FREQUENCIES;
 use crate::finder::desktop::terminal::create_terminal_command;
+use crate::finder::search::Search;
 use crate::finder::{Entry, Finder};
 
 mod entry;
 pub mod frequency;
+mod index;
 mod terminal;
 
+/// Name of the finder, used to restrict the results to it.
+pub const NAME: &str = "desktop";
+
+/// How much choosing an entry after typing a similar query boosts it, per point of frequency.
+const QUERY_SCORE_WEIGHT: u32 = 8;
+
 pub struct DesktopFinder {
     entries: Vec<Rc<DesktopEntry>>,
-    frequencies: Frequencies,
+    /// The haystacks of the entries, shared with the searches running in the background.
+    haystacks: Arc<[Haystack]>,
+}
+
+impl DesktopFinder {
+    fn set_entries(&mut self, entries: Vec<Rc<DesktopEntry>>) {
+        self.haystacks = entries.iter().map(|entry| entry.haystack()).collect();
+        self.entries = entries;
+    }
 }
 
 impl Finder for DesktopFinder {
     fn new() -> Self {
-        Self {
-            entries: DesktopEntry::load(),
-            frequencies: Frequencies::load(),
+        let mut finder = Self {
+            entries: Vec::new(),
+            haystacks: Arc::new([]),
+        };
+        finder.set_entries(DesktopEntry::load());
+        finder
+    }
+
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn reload(&mut self) {
+        index::invalidate();
+        self.set_entries(DesktopEntry::load());
+    }
+
+    fn watched_dirs(&self) -> Vec<PathBuf> {
+        let dirs = freedesktop_desktop_entry::default_paths().collect::<Vec<_>>();
+        index::lookup_dirs(&dirs)
+    }
+
+    fn reload_file(&mut self, path: &Path) {
+        if path
+            .extension()
+            .is_none_or(|extension| extension != "desktop")
+        {
+            return;
         }
+        let Some(id) = path.file_stem() else {
+            return;
+        };
+        index::invalidate();
+
+        // The file may shadow, or be shadowed by, one with the same id in another directory or
+        // subdirectory
+        let id = SharedString::from(id.to_string_lossy().into_owned());
+        let mut entries = std::mem::take(&mut self.entries);
+        entries.retain(|entry| entry.id != id && entry.parent.as_ref() != Some(&id));
+        entries.extend(DesktopEntry::load_id(&id).into_iter().map(Rc::new));
+        self.set_entries(entries);
     }
 
     fn default_entries(&self) -> Option<Vec<Rc<dyn Entry>>> {
         Some(
             self.entries
                 .iter()
+                .filter(|entry| !entry.is_action())
                 .map(|entry| {
-                    entry.set_score(self.frequencies.score(&entry.id()));
-                    entry.clone() as Rc<dyn Entry>
+                    let score = DESKTOP_FREQUENCIES.score(&entry.id);
+                    Rc::new(DesktopResult::new(entry.clone(), score, &[])) as Rc<dyn Entry>
                 })
                 .collect(),
         )
     }
 
-    fn filtered_entries(
-        &self,
-        matcher: &mut nucleo_matcher::Matcher,
-        search_term: &str,
-    ) -> Option<Vec<Rc<dyn Entry>>> {
+    fn search(&self, search_term: &str) -> Option<Search> {
         let search_pattern = Pattern::new(
             search_term,
             CaseMatching::Ignore,
             Normalization::Smart,
             AtomKind::Fuzzy,
         );
+        let haystacks = self.haystacks.clone();
+        let entries = self.entries.clone();
+        let search_term = search_term.to_string();
 
-        Some(
-            self.entries
-                .iter()
-                .filter_map(|entry| {
-                    search_pattern
-                        .score(entry.haystack.slice(..), matcher)
-                        .map(|score| {
-                            entry.set_score(score);
-                            entry.clone() as Rc<dyn Entry>
-                        })
-                })
-                .collect(),
-        )
+        Some(Search::new(
+            move |matcher| {
+                haystacks
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, haystack)| {
+                        haystack.matches(index, &search_pattern, matcher)
+                    })
+                    .collect()
+            },
+            move |matches| {
+                let query_scores = DESKTOP_FREQUENCIES.query_scores(&search_term);
+                matches
+                    .into_iter()
+                    .map(|m| {
+                        let entry = entries[m.index].clone();
+                        let learned = query_scores.get(&entry.id).copied().unwrap_or_default();
+                        let score = m.score + learned * QUERY_SCORE_WEIGHT;
+                        Rc::new(DesktopResult::new(entry, score, &m.indices)) as Rc<dyn Entry>
+                    })
+                    .collect()
+            },
+        ))
     }
 }
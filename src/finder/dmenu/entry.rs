@@ -0,0 +1,85 @@
+use gpui::{App, Resource, SharedString, Window};
+
+use crate::finder::dmenu::finish;
+use crate::finder::search::highlight_ranges;
+use crate::finder::{Entry, Highlights, dmenu};
+use crate::ui::elements::DropdownItem;
+
+pub struct DmenuEntry {
+    id: SharedString,
+    text: SharedString,
+    score: u32,
+    /// The positions of the characters of the text that matched.
+    indices: Vec<u32>,
+    /// If this entry is the raw search term rather than one of the items.
+    is_query: bool,
+}
+
+impl DmenuEntry {
+    pub fn new(index: usize, text: SharedString, score: u32, indices: Vec<u32>) -> Self {
+        Self {
+            // Items aren't necessarily unique, so they are identified by their position
+            id: format!("dmenu:{}", index).into(),
+            text,
+            score,
+            indices,
+            is_query: false,
+        }
+    }
+
+    pub fn query(text: SharedString) -> Self {
+        Self {
+            id: "dmenu:query".into(),
+            text,
+            score: 0,
+            indices: Vec::new(),
+            is_query: true,
+        }
+    }
+}
+
+impl Entry for DmenuEntry {
+    fn id(&self) -> SharedString {
+        self.id.clone()
+    }
+
+    fn finder(&self) -> &'static str {
+        dmenu::NAME
+    }
+
+    fn score(&self) -> u32 {
+        self.score
+    }
+
+    fn text(&self) -> SharedString {
+        self.text.clone()
+    }
+
+    fn description(&self) -> Option<SharedString> {
+        self.is_query.then(|| "Use the search term".into())
+    }
+
+    fn icon(&self) -> Option<Resource> {
+        None
+    }
+
+    fn highlights(&self) -> Highlights {
+        Highlights {
+            text: highlight_ranges(&self.text, &self.indices),
+            description: Vec::new(),
+        }
+    }
+
+    fn can_favorite(&self) -> bool {
+        false
+    }
+
+    fn actions(&self) -> Vec<DropdownItem> {
+        Vec::new()
+    }
+
+    fn execute(&self, window: &mut Window, _cx: &mut App) {
+        finish(Some(self.text.to_string()));
+        window.remove_window();
+    }
+}
@@ -0,0 +1,118 @@
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+use gpui::SharedString;
+use nucleo_matcher::pattern::{AtomKind, CaseMatching, Normalization, Pattern};
+
+use crate::finder::dmenu::entry::DmenuEntry;
+use crate::finder::search::{Match, Search};
+use crate::finder::{Entry, Finder};
+
+mod entry;
+
+/// Name of the finder, used as the mode of the window showing a dmenu session.
+pub const NAME: &str = "dmenu";
+
+/// The choices of the running dmenu session, if any.
+static DMENU_SESSION: Mutex<Option<DmenuSession>> = Mutex::new(None);
+
+struct DmenuSession {
+    items: Arc<[SharedString]>,
+    /// If the search term itself can be chosen, when it isn't one of the items.
+    print_query: bool,
+    sender: smol::channel::Sender<Option<String>>,
+}
+
+/// Starts a dmenu session offering `items`, replacing any running one.
+/// The returned channel receives the user's choice, or `None` if the session was cancelled.
+pub fn start(items: Vec<String>, print_query: bool) -> smol::channel::Receiver<Option<String>> {
+    let (sender, receiver) = smol::channel::bounded(1);
+    *DMENU_SESSION.lock().unwrap() = Some(DmenuSession {
+        items: items.into_iter().map(SharedString::from).collect(),
+        print_query,
+        sender,
+    });
+    receiver
+}
+
+/// Ends the running dmenu session, if any, with the user's choice.
+pub fn finish(choice: Option<String>) {
+    if let Some(session) = DMENU_SESSION.lock().unwrap().take() {
+        let _ = session.sender.try_send(choice);
+    }
+}
+
+/// If a dmenu session is running, the only time the dmenu mode can be selected.
+pub fn is_running() -> bool {
+    DMENU_SESSION.lock().unwrap().is_some()
+}
+
+pub struct DmenuFinder {}
+
+impl Finder for DmenuFinder {
+    fn new() -> Self {
+        Self {}
+    }
+
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn default_entries(&self) -> Option<Vec<Rc<dyn Entry>>> {
+        None
+    }
+
+    fn search(&self, search_term: &str) -> Option<Search> {
+        let session = DMENU_SESSION.lock().unwrap();
+        let session = session.as_ref()?;
+
+        let search_pattern = Pattern::new(
+            search_term,
+            CaseMatching::Ignore,
+            Normalization::Smart,
+            AtomKind::Fuzzy,
+        );
+        let items = session.items.clone();
+        let query = (session.print_query
+            && !search_term.is_empty()
+            && !session.items.iter().any(|item| item == search_term))
+        .then(|| SharedString::from(search_term.to_string()));
+
+        Some(Search::new(
+            {
+                let items = items.clone();
+                move |matcher| {
+                    items
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(index, item)| {
+                            Match::new(
+                                index,
+                                &search_pattern,
+                                nucleo_matcher::Utf32String::from(item.as_ref()).slice(..),
+                                matcher,
+                            )
+                        })
+                        .collect()
+                }
+            },
+            move |matches| {
+                let mut entries = matches
+                    .into_iter()
+                    .map(|m| {
+                        Rc::new(DmenuEntry::new(
+                            m.index,
+                            items[m.index].clone(),
+                            m.score,
+                            m.indices,
+                        )) as Rc<dyn Entry>
+                    })
+                    .collect::<Vec<_>>();
+                if let Some(query) = query {
+                    entries.push(Rc::new(DmenuEntry::query(query)));
+                }
+                entries
+            },
+        ))
+    }
+}
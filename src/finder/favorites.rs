@@ -6,7 +6,7 @@ use std::sync::LazyLock;
 use gpui::{Global, SharedString};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Favorites {
     favorites: HashSet<SharedString>,
 }
@@ -23,6 +23,9 @@ impl Favorites {
 
     pub async fn save(&self) {
         let content = toml::to_string(self).expect("Failed to serialize favorites");
+        if let Some(dir) = FAVORITES_SAVE_PATH.parent() {
+            smol::fs::create_dir_all(dir).await.ok();
+        }
         if let Err(err) = smol::fs::write(&*FAVORITES_SAVE_PATH, content).await {
             eprintln!(
                 "Failed to save favorites at {}: {}",
@@ -49,8 +52,9 @@ impl DerefMut for Favorites {
 
 impl Global for Favorites {}
 
-static FAVORITES_SAVE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
-    dirs::data_dir()
-        .expect("Failed to get data directory")
-        .join("waystart.toml")
+pub(crate) static FAVORITES_SAVE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    dirs::state_dir()
+        .expect("Failed to get state directory")
+        .join("waystart")
+        .join("favorites.toml")
 });
@@ -0,0 +1,58 @@
+use std::collections::HashSet;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use gpui::{Global, SharedString};
+use serde::{Deserialize, Serialize};
+
+use crate::finder::saves;
+
+/// Entries the user asked to never show in the results.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Hidden {
+    hidden: HashSet<SharedString>,
+}
+
+impl Hidden {
+    pub fn load() -> Self {
+        saves::load(&HIDDEN_SAVE_PATH, "hidden entries")
+    }
+
+    pub async fn save(&self) {
+        let content = toml::to_string(self).expect("Failed to serialize hidden entries");
+        if let Some(dir) = HIDDEN_SAVE_PATH.parent() {
+            smol::fs::create_dir_all(dir).await.ok();
+        }
+        if let Err(err) = smol::fs::write(&*HIDDEN_SAVE_PATH, content).await {
+            eprintln!(
+                "Failed to save hidden entries at {}: {}",
+                HIDDEN_SAVE_PATH.to_string_lossy(),
+                err
+            );
+        }
+    }
+}
+
+impl Deref for Hidden {
+    type Target = HashSet<SharedString>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.hidden
+    }
+}
+
+impl DerefMut for Hidden {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.hidden
+    }
+}
+
+impl Global for Hidden {}
+
+pub(crate) static HIDDEN_SAVE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    dirs::state_dir()
+        .expect("Failed to get state directory")
+        .join("waystart")
+        .join("hidden.toml")
+});
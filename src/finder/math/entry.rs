@@ -1,6 +1,7 @@
 use gpui::{App, Resource, SharedString, Window};
 
-use crate::finder::Entry;
+use crate::finder::{Entry, math};
+use crate::ui::elements::DropdownItem;
 
 pub struct MathEntry {
     pub result: evalexpr::Value,
@@ -12,6 +13,10 @@ impl Entry for MathEntry {
         self.text.clone()
     }
 
+    fn finder(&self) -> &'static str {
+        math::NAME
+    }
+
     fn score(&self) -> u32 {
         u32::MAX
     }
@@ -32,6 +37,10 @@ impl Entry for MathEntry {
         false
     }
 
+    fn actions(&self) -> Vec<DropdownItem> {
+        Vec::new()
+    }
+
     fn execute(&self, window: &mut Window, cx: &mut App) {
         cx.write_to_clipboard(self.result.to_string().into());
         window.remove_window();
@@ -1,9 +1,13 @@
 use std::rc::Rc;
 
+use crate::finder::search::Search;
 use crate::finder::{Entry, Finder, math::entry::MathEntry};
 
 mod entry;
 
+/// Name of the finder, used to restrict the results to it.
+pub const NAME: &str = "math";
+
 pub struct MathFinder {}
 
 impl Finder for MathFinder {
@@ -11,25 +15,26 @@ impl Finder for MathFinder {
         Self {}
     }
 
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
     fn default_entries(&self) -> Option<Vec<Rc<dyn Entry>>> {
         None
     }
 
-    fn filtered_entries(
-        &self,
-        _matcher: &mut nucleo_matcher::Matcher,
-        search_term: &str,
-    ) -> Option<Vec<Rc<dyn Entry>>> {
+    fn search(&self, search_term: &str) -> Option<Search> {
+        // Expressions are quick to evaluate, so they don't need to run in the background
         if let Some(search_term) = search_term.strip_prefix('=') {
             return match evalexpr::eval(search_term) {
-                Ok(result) => Some(vec![Rc::new(MathEntry {
+                Ok(result) => Some(Search::ready(vec![Rc::new(MathEntry {
                     text: format!("= {}", result).into(),
                     result,
-                })]),
-                Err(err) => Some(vec![Rc::new(MathEntry {
+                })])),
+                Err(err) => Some(Search::ready(vec![Rc::new(MathEntry {
                     text: err.to_string().into(),
                     result: evalexpr::Value::String(err.to_string()),
-                })]),
+                })])),
             };
         }
 
@@ -37,10 +42,10 @@ impl Finder for MathFinder {
         if let evalexpr::Value::Empty = result {
             None
         } else {
-            Some(vec![Rc::new(MathEntry {
+            Some(Search::ready(vec![Rc::new(MathEntry {
                 text: format!("= {}", result).into(),
                 result,
-            })])
+            })]))
         }
     }
 }
@@ -1,21 +1,31 @@
-use std::cell::RefCell;
 use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
-use gpui::{App, Global, Resource, SharedString, Window};
+use gpui::{App, BackgroundExecutor, Global, Resource, SharedString, Window};
 
 use crate::finder::desktop::DesktopFinder;
+use crate::finder::dmenu::DmenuFinder;
 use crate::finder::math::MathFinder;
+use crate::finder::search::{RunningSearch, Search};
 use crate::finder::wifi::WifiFinder;
+use crate::ui::elements::DropdownItem;
+use crate::watcher;
 
 pub mod desktop;
+pub mod dmenu;
 pub mod favorites;
+pub mod hidden;
 pub mod math;
+pub mod saves;
+pub mod search;
 pub mod wifi;
 
 pub struct Finders {
     finders: Vec<Box<dyn Finder>>,
-    matcher: RefCell<nucleo_matcher::Matcher>,
+    mode: Option<String>,
 }
 
 impl Global for Finders {}
@@ -26,29 +36,182 @@ impl Finders {
                 Box::new(DesktopFinder::new()),
                 Box::new(MathFinder::new()),
                 Box::new(WifiFinder::new()),
+                Box::new(DmenuFinder::new()),
             ],
-            matcher: RefCell::new(nucleo_matcher::Matcher::default()),
+            mode: None,
         }
     }
 
-    pub fn default_entries(&self) -> Vec<Rc<dyn Entry>> {
-        let mut entries = self
+    /// Restricts the results to the finder with the given name, or lifts the restriction.
+    pub fn set_mode(&mut self, mode: Option<&str>) -> Result<(), String> {
+        if let Some(mode) = mode {
+            self.check_mode(mode)?;
+        }
+
+        self.mode = mode.map(str::to_string);
+        Ok(())
+    }
+
+    pub fn mode(&self) -> Option<&str> {
+        self.mode.as_deref()
+    }
+
+    /// Checks that a finder is named `mode`.
+    ///
+    /// The dmenu finder can only be selected by the window showing a dmenu session.
+    pub fn check_mode(&self, mode: &str) -> Result<(), String> {
+        let selectable = self
             .finders
+            .iter()
+            .map(|finder| finder.name())
+            .filter(|&name| name != dmenu::NAME || dmenu::is_running())
+            .collect::<Vec<_>>();
+
+        if mode == dmenu::NAME && !dmenu::is_running() {
+            return Err("The dmenu mode is only available through `waystart dmenu`".to_string());
+        }
+        if !selectable.contains(&mode) {
+            return Err(format!(
+                "Unknown mode `{}`, expected one of: {}",
+                mode,
+                selectable.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    fn active_finders(&self, mode: Option<&str>) -> impl Iterator<Item = &Box<dyn Finder>> {
+        self.finders
+            .iter()
+            .filter(move |finder| mode.is_none_or(|mode| finder.name() == mode))
+    }
+
+    /// Reloads every finder, returning the ids of the entries that appeared and disappeared.
+    pub fn reload(&mut self) -> (Vec<SharedString>, Vec<SharedString>) {
+        let before = self.entry_ids();
+        for finder in &mut self.finders {
+            finder.reload();
+        }
+        let after = self.entry_ids();
+
+        (
+            after.difference(&before).cloned().collect(),
+            before.difference(&after).cloned().collect(),
+        )
+    }
+
+    /// Keeps the finders up to date with the files their entries are loaded from.
+    pub fn watch(cx: &mut App) {
+        let dirs = cx
+            .global::<Finders>()
+            .finders
+            .iter()
+            .flat_map(|finder| finder.watched_dirs())
+            .collect::<Vec<_>>();
+
+        // Directories that don't exist yet are watched through their nearest existing ancestor
+        let mut watched = Vec::<PathBuf>::new();
+        for dir in &dirs {
+            if let Some(existing) = dir.ancestors().find(|ancestor| ancestor.is_dir())
+                && !watched.iter().any(|watched| watched == existing)
+            {
+                watched.push(existing.to_path_buf());
+            }
+        }
+
+        // Profiles, like nix's, swap a symlink to the directory rather than changing its content
+        let links = dirs
+            .iter()
+            .flat_map(|dir| watcher::symlinks_in(dir))
+            .collect::<HashSet<_>>();
+        for parent in links.iter().filter_map(|link| link.parent()) {
+            if !watched.iter().any(|dir| dir == parent) {
+                watched.push(parent.to_path_buf());
+            }
+        }
+
+        let changes = match watcher::watch(&watched) {
+            Ok(changes) => changes,
+            Err(err) => {
+                eprintln!("Failed to watch the application directories: {}", err);
+                return;
+            }
+        };
+
+        cx.spawn(async move |cx| {
+            while let Ok(path) = changes.recv().await {
+                // A directory appeared or disappeared on the way to, or inside, the watched ones,
+                // such as a new subdirectory or a missing directory being created
+                let dir_changed = (path.is_dir() || dirs.contains(&path))
+                    && dirs
+                        .iter()
+                        .any(|dir| dir.starts_with(&path) || path.parent() == Some(dir));
+
+                if dir_changed || links.contains(&path) {
+                    // The directories changed, so everything is rewatched and reloaded, in this
+                    // order so files added meanwhile aren't missed
+                    cx.update(|cx| {
+                        Finders::watch(cx);
+                        cx.global_mut::<Finders>().reload();
+                        cx.refresh_windows();
+                    });
+                    return;
+                }
+
+                if dirs.iter().any(|dir| path.parent() == Some(dir)) {
+                    cx.update(|cx| {
+                        cx.global_mut::<Finders>().reload_file(&path);
+                        cx.refresh_windows();
+                    });
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// Lets the finders know that `path` was created, changed or deleted.
+    pub fn reload_file(&mut self, path: &Path) {
+        for finder in &mut self.finders {
+            finder.reload_file(path);
+        }
+    }
+
+    fn entry_ids(&self) -> HashSet<SharedString> {
+        self.finders
             .iter()
             .filter_map(|finder| finder.default_entries())
             .flatten()
+            .map(|entry| entry.id())
+            .collect()
+    }
+
+    pub fn default_entries(&self) -> Vec<Rc<dyn Entry>> {
+        let mut entries = self
+            .active_finders(self.mode())
+            .filter_map(|finder| finder.default_entries())
+            .flatten()
             .collect::<Vec<_>>();
         entries.sort_by_key(|entry| Reverse(entry.score()));
         entries
     }
 
-    pub fn filtered_entries(&self, search_term: &str) -> Vec<Rc<dyn Entry>> {
-        let mut matcher = self.matcher.borrow_mut();
+    /// Searches the entries that match `search_term` on the background executor, so slow finders
+    /// don't block the main thread.
+    pub fn spawn_search(&self, search_term: &str, executor: &BackgroundExecutor) -> RunningSearch {
+        let searches = self
+            .active_finders(self.mode())
+            .filter_map(|finder| finder.search(search_term))
+            .collect();
+        RunningSearch::spawn(searches, executor)
+    }
+
+    /// Returns the entries that match `search_term`, only from the finder named `mode` if given.
+    pub fn search(&self, search_term: &str, mode: Option<&str>) -> Vec<Rc<dyn Entry>> {
+        let mut matcher = nucleo_matcher::Matcher::default();
         let mut entries = self
-            .finders
-            .iter()
-            .filter_map(|finder| finder.filtered_entries(&mut matcher, search_term))
-            .flatten()
+            .active_finders(mode)
+            .filter_map(|finder| finder.search(search_term))
+            .flat_map(|search| search.run(&mut matcher))
             .collect::<Vec<_>>();
         entries.sort_by_key(|entry| Reverse(entry.score()));
         entries
@@ -60,21 +223,35 @@ pub trait Finder {
     where
         Self: Sized;
 
+    /// Get the name of this finder, used to restrict the results to it.
+    fn name(&self) -> &'static str;
+
+    /// Reloads the entries from their source, if they are cached.
+    fn reload(&mut self) {}
+
+    /// Get the directories holding the files the entries are loaded from, including the
+    /// subdirectories and the directories that don't exist yet.
+    fn watched_dirs(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Reloads the entries loaded from `path`, after it was created, changed or deleted.
+    fn reload_file(&mut self, _path: &Path) {}
+
     /// Returns the entries when no search is performed.
     fn default_entries(&self) -> Option<Vec<Rc<dyn Entry>>>;
 
-    /// Returns the entries that match the given pattern.
-    fn filtered_entries(
-        &self,
-        matcher: &mut nucleo_matcher::Matcher,
-        search_term: &str,
-    ) -> Option<Vec<Rc<dyn Entry>>>;
+    /// Prepares a search for the entries that match the given pattern.
+    fn search(&self, search_term: &str) -> Option<Search>;
 }
 
 pub trait Entry {
     /// Get a unique identifier for this entry.
     fn id(&self) -> SharedString;
 
+    /// Get the name of the finder this entry comes from.
+    fn finder(&self) -> &'static str;
+
     /// Get a unique identifier for this entry.
     fn score(&self) -> u32;
 
@@ -87,9 +264,24 @@ pub trait Entry {
     /// Get the icon of this entry.
     fn icon(&self) -> Option<Resource>;
 
+    /// Get the parts of the text and description that matched the search term.
+    fn highlights(&self) -> Highlights {
+        Highlights::default()
+    }
+
     /// If this entry can be favorited.
     fn can_favorite(&self) -> bool;
 
+    /// Get the secondary actions specific to this entry.
+    fn actions(&self) -> Vec<DropdownItem>;
+
     /// Execute this entry per user's request.
     fn execute(&self, window: &mut Window, cx: &mut App);
 }
+
+/// The byte ranges of an entry's text and description that matched the search term.
+#[derive(Clone, Default)]
+pub struct Highlights {
+    pub text: Vec<Range<usize>>,
+    pub description: Vec<Range<usize>>,
+}
@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use gpui::{App, AsyncApp, Global, Task};
+use serde::de::DeserializeOwned;
+
+/// The saves of the state files, written one after the other in the background.
+#[derive(Default)]
+pub struct Saves {
+    /// The last save, which waits for the previous ones before writing.
+    last: Option<Task<()>>,
+}
+
+impl Global for Saves {}
+
+impl Saves {
+    /// Runs `save` in the background, once the previous saves are written.
+    pub fn spawn(save: impl Future<Output = ()> + Send + 'static, cx: &mut App) {
+        let previous = cx.default_global::<Saves>().last.take();
+        let task = cx.background_spawn(async move {
+            if let Some(previous) = previous {
+                previous.await;
+            }
+            save.await;
+        });
+        cx.default_global::<Saves>().last = Some(task);
+    }
+
+    /// Waits for every save to be written, so the files can be read back.
+    pub async fn wait(cx: &mut AsyncApp) {
+        let last = cx.update(|cx| cx.default_global::<Saves>().last.take());
+        if let Some(last) = last {
+            last.await;
+        }
+    }
+}
+
+/// Reads the state file at `path`, falling back to the default value when it is missing or
+/// can't be parsed.
+pub fn load<T: DeserializeOwned + Default>(path: &Path, name: &str) -> T {
+    let Ok(file) = std::fs::read_to_string(path) else {
+        return T::default();
+    };
+    toml::from_str(&file).unwrap_or_else(|err| {
+        eprintln!(
+            "Failed to parse {} at {}, starting over: {}",
+            name,
+            path.to_string_lossy(),
+            err
+        );
+        T::default()
+    })
+}
@@ -0,0 +1,163 @@
+use std::ops::Range;
+use std::rc::Rc;
+
+use gpui::{BackgroundExecutor, Task};
+use nucleo_matcher::Utf32Str;
+use nucleo_matcher::pattern::Pattern;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::finder::Entry;
+
+/// A candidate of a finder that matched the search term.
+pub struct Match {
+    /// The position of the candidate in the finder's snapshot.
+    pub index: usize,
+    pub score: u32,
+    /// The sorted positions of the characters of the haystack that matched.
+    pub indices: Vec<u32>,
+}
+
+impl Match {
+    /// Matches the candidate at `index` against `pattern`, keeping the matched characters.
+    pub fn new(
+        index: usize,
+        pattern: &Pattern,
+        haystack: Utf32Str,
+        matcher: &mut nucleo_matcher::Matcher,
+    ) -> Option<Self> {
+        let mut indices = Vec::new();
+        let score = pattern.indices(haystack, matcher, &mut indices)?;
+        // Each atom of the pattern adds its own indices
+        indices.sort_unstable();
+        indices.dedup();
+
+        Some(Self {
+            index,
+            score,
+            indices,
+        })
+    }
+}
+
+/// Returns the byte ranges of `text` holding the characters at the given sorted positions.
+///
+/// Haystacks hold a single character per grapheme, so positions are counted in graphemes.
+pub fn highlight_ranges(text: &str, indices: &[u32]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::<Range<usize>>::new();
+    let mut indices = indices.iter().peekable();
+
+    for (i, (start, grapheme)) in text.grapheme_indices(true).enumerate() {
+        let Some(&&index) = indices.peek() else {
+            break;
+        };
+        if index as usize != i {
+            continue;
+        }
+        indices.next();
+
+        let end = start + grapheme.len();
+        match ranges.last_mut() {
+            Some(range) if range.end == start => range.end = end,
+            _ => ranges.push(start..end),
+        }
+    }
+
+    ranges
+}
+
+type MatchFn = Box<dyn FnOnce(&mut nucleo_matcher::Matcher) -> Vec<Match> + Send>;
+type EntriesFn = Box<dyn FnOnce(Vec<Match>) -> Vec<Rc<dyn Entry>>>;
+
+/// A search prepared by a finder, whose matching can run away from the main thread.
+pub struct Search {
+    /// Scores a snapshot of the finder's candidates against the search term.
+    matches: MatchFn,
+    /// Creates the entries of the candidates that matched, on the main thread.
+    entries: EntriesFn,
+}
+
+impl Search {
+    pub fn new(
+        matches: impl FnOnce(&mut nucleo_matcher::Matcher) -> Vec<Match> + Send + 'static,
+        entries: impl FnOnce(Vec<Match>) -> Vec<Rc<dyn Entry>> + 'static,
+    ) -> Self {
+        Self {
+            matches: Box::new(matches),
+            entries: Box::new(entries),
+        }
+    }
+
+    /// Creates a search whose entries are already known, for finders that are quick to match.
+    pub fn ready(entries: Vec<Rc<dyn Entry>>) -> Self {
+        Self::new(|_| Vec::new(), move |_| entries)
+    }
+
+    /// Runs the search on the current thread.
+    pub fn run(self, matcher: &mut nucleo_matcher::Matcher) -> Vec<Rc<dyn Entry>> {
+        (self.entries)((self.matches)(matcher))
+    }
+}
+
+/// Searches running on the background executor, yielding the entries of each finder as soon as
+/// it is done.
+///
+/// Dropping it cancels the searches that are still running.
+pub struct RunningSearch {
+    _tasks: Vec<Task<()>>,
+    matches: smol::channel::Receiver<(usize, Vec<Match>)>,
+    entries: Vec<Option<EntriesFn>>,
+}
+
+impl RunningSearch {
+    pub fn spawn(searches: Vec<Search>, executor: &BackgroundExecutor) -> Self {
+        let (sender, receiver) = smol::channel::unbounded();
+        let mut tasks = Vec::with_capacity(searches.len());
+        let mut entries = Vec::with_capacity(searches.len());
+
+        for (i, search) in searches.into_iter().enumerate() {
+            let sender = sender.clone();
+            let matches = search.matches;
+            tasks.push(executor.spawn(async move {
+                let mut matcher = nucleo_matcher::Matcher::default();
+                sender.send((i, matches(&mut matcher))).await.ok();
+            }));
+            entries.push(Some(search.entries));
+        }
+
+        Self {
+            _tasks: tasks,
+            matches: receiver,
+            entries,
+        }
+    }
+
+    /// Waits for the next finder to be done, returning its entries, or `None` once all are done.
+    pub async fn next(&mut self) -> Option<Vec<Rc<dyn Entry>>> {
+        let (i, matches) = self.matches.recv().await.ok()?;
+        let entries = self.entries.get_mut(i)?.take()?;
+        Some(entries(matches))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::highlight_ranges;
+
+    #[test]
+    fn merges_adjacent_characters() {
+        assert_eq!(highlight_ranges("Firefox", &[0, 1, 2, 4]), [0..3, 4..5]);
+    }
+
+    #[test]
+    fn counts_positions_in_graphemes() {
+        // "é" is two code points and three bytes, the flag two code points and eight bytes
+        let text = "Cafe\u{301} 🇫🇷 menu";
+        assert_eq!(highlight_ranges(text, &[3, 5, 7]), [3..6, 7..15, 16..17]);
+    }
+
+    #[test]
+    fn ignores_positions_past_the_end() {
+        assert_eq!(highlight_ranges("abc", &[2, 3, 10]), [2..3]);
+        assert!(highlight_ranges("abc", &[]).is_empty());
+    }
+}
@@ -1,48 +1,111 @@
 use gpui::{App, Resource, SharedString, Window};
 
-use crate::finder::{Entry, wifi::WifiManager};
+use crate::finder::search::highlight_ranges;
+use crate::finder::wifi::{WifiNetwork, connect};
+use crate::finder::{Entry, Highlights, wifi};
+use crate::ui::elements::DropdownItem;
 
 pub struct WifiEntry {
-    pub network: nmrs::Network,
+    pub network: WifiNetwork,
+    score: u32,
+    /// The positions of the characters of the SSID that matched.
+    indices: Vec<u32>,
+    icon: Option<Resource>,
+}
+
+impl WifiEntry {
+    pub fn new(network: WifiNetwork, score: u32, indices: Vec<u32>) -> Self {
+        let strength = network.network.strength.unwrap_or_default();
+
+        let icon_name = match strength {
+            80.. => "network-wireless-signal-excellent",
+            55..80 => "network-wireless-signal-good",
+            30..55 => "network-wireless-signal-ok",
+            5..30 => "network-wireless-signal-weak",
+            _ => "network-wireless-signal-none",
+        };
+        let icon = freedesktop_icons::lookup(icon_name)
+            .with_cache()
+            .with_size(28)
+            .find()
+            .map(|path| Resource::Path(path.into()));
+
+        Self {
+            network,
+            score,
+            indices,
+            icon,
+        }
+    }
 }
 
 impl Entry for WifiEntry {
     fn id(&self) -> SharedString {
-        self.network.ssid.clone().into()
+        self.network.network.ssid.clone().into()
+    }
+
+    fn finder(&self) -> &'static str {
+        wifi::NAME
     }
 
     fn score(&self) -> u32 {
-        self.network.strength.unwrap_or_default() as u32
+        self.score
     }
 
     fn text(&self) -> SharedString {
-        self.network.ssid.clone().into()
+        self.network.network.ssid.clone().into()
     }
 
     fn description(&self) -> Option<SharedString> {
-        None
+        let network = &self.network.network;
+        let state = if self.network.connected {
+            Some("Connected")
+        } else if self.network.known {
+            Some("Saved")
+        } else {
+            None
+        };
+        let strength = network.strength.map(|strength| format!("{}%", strength));
+        let security = if !network.secured {
+            "Open"
+        } else if network.is_psk {
+            "WPA-PSK"
+        } else {
+            "Secured"
+        };
+
+        Some(
+            state
+                .map(str::to_string)
+                .into_iter()
+                .chain(strength)
+                .chain([security.to_string()])
+                .collect::<Vec<_>>()
+                .join(" · ")
+                .into(),
+        )
     }
 
     fn icon(&self) -> Option<Resource> {
-        None
+        self.icon.clone()
+    }
+
+    fn highlights(&self) -> Highlights {
+        Highlights {
+            text: highlight_ranges(&self.network.network.ssid, &self.indices),
+            description: Vec::new(),
+        }
     }
 
     fn can_favorite(&self) -> bool {
         false
     }
 
+    fn actions(&self) -> Vec<DropdownItem> {
+        Vec::new()
+    }
+
     fn execute(&self, window: &mut Window, cx: &mut App) {
-        let nm = cx.global::<WifiManager>();
-        if !self.network.secured {
-            nm.connect(&self.network, nmrs::WifiSecurity::Open);
-        } else if self.network.is_psk {
-            nm.connect(
-                &self.network,
-                nmrs::WifiSecurity::WpaPsk {
-                    psk: "".to_string(),
-                },
-            );
-        }
-        window.remove_window();
+        connect(&self.network, window, cx);
     }
 }
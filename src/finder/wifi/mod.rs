@@ -1,57 +1,240 @@
+use std::cmp::Reverse;
 use std::rc::Rc;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
 
+use gpui::{App, Window, WindowHandle};
 use nucleo_matcher::pattern::{AtomKind, CaseMatching, Normalization, Pattern};
 
+use crate::finder::search::{Match, Search};
 use crate::finder::wifi::entry::WifiEntry;
 use crate::finder::{Entry, Finder};
+use crate::ui::Waystart;
 
 mod entry;
 mod nm;
 
+/// Name of the finder, used to restrict the results to it.
+pub const NAME: &str = "wifi";
+
 pub use nm::WifiManager;
 
-pub struct WifiFinder {
-    entries: Vec<Rc<WifiEntry>>,
+/// The networks found by the last scan, shared between the finder and the scanning task.
+static WIFI_NETWORKS: LazyLock<Mutex<Arc<[WifiNetwork]>>> = LazyLock::new(Default::default);
+/// If the Wi-Fi radio was enabled during the last scan, unknown until the first one.
+static WIFI_ENABLED: Mutex<Option<bool>> = Mutex::new(None);
+
+const SCAN_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+pub struct WifiNetwork {
+    pub network: nmrs::Network,
+    /// If NetworkManager has a saved connection for this network.
+    pub known: bool,
+    pub connected: bool,
 }
 
+pub struct WifiFinder {}
+
 impl Finder for WifiFinder {
     fn new() -> Self {
-        Self { entries: vec![] }
+        Self {}
+    }
+
+    fn name(&self) -> &'static str {
+        NAME
     }
 
     fn default_entries(&self) -> Option<Vec<Rc<dyn Entry>>> {
-        Some(
-            self.entries
-                .iter()
-                .map(|entry| entry.clone() as Rc<dyn Entry>)
-                .collect(),
-        )
-    }
-
-    fn filtered_entries(
-        &self,
-        matcher: &mut nucleo_matcher::Matcher,
-        search_term: &str,
-    ) -> Option<Vec<Rc<dyn Entry>>> {
+        None
+    }
+
+    fn search(&self, search_term: &str) -> Option<Search> {
         let search_pattern = Pattern::new(
             search_term,
             CaseMatching::Ignore,
             Normalization::Smart,
             AtomKind::Fuzzy,
         );
+        let networks = networks();
+
+        Some(Search::new(
+            {
+                let networks = networks.clone();
+                move |matcher| {
+                    networks
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(index, network)| {
+                            let ssid =
+                                nucleo_matcher::Utf32String::from(network.network.ssid.as_str());
+                            Match::new(index, &search_pattern, ssid.slice(..), matcher)
+                        })
+                        .collect()
+                }
+            },
+            move |matches| {
+                let candidates = matches
+                    .iter()
+                    .map(|m| Candidate {
+                        known: networks[m.index].known,
+                        score: m.score,
+                        strength: networks[m.index].network.strength.unwrap_or_default(),
+                    })
+                    .collect::<Vec<_>>();
+
+                matches
+                    .into_iter()
+                    .zip(rank(&candidates))
+                    .map(|(m, score)| {
+                        let network = networks[m.index].clone();
+                        Rc::new(WifiEntry::new(network, score, m.indices)) as Rc<dyn Entry>
+                    })
+                    .collect()
+            },
+        ))
+    }
+}
+
+/// Returns the networks found by the last scan.
+pub fn networks() -> Arc<[WifiNetwork]> {
+    WIFI_NETWORKS.lock().unwrap().clone()
+}
+
+/// Returns if the Wi-Fi radio was enabled during the last scan, or `None` before the first one.
+pub fn enabled() -> Option<bool> {
+    *WIFI_ENABLED.lock().unwrap()
+}
+
+/// Connects to `network`, asking the user for its password first if it's needed.
+pub fn connect(network: &WifiNetwork, window: &mut Window, cx: &mut App) {
+    let prompt = !network.known && network.network.secured;
+    if prompt && !network.network.is_psk {
+        eprintln!(
+            "Failed to connect to {}: Unsupported security.",
+            network.network.ssid
+        );
+        return;
+    }
+
+    // Defer, as the Waystart entity is likely being updated already
+    let network = network.network.clone();
+    window.defer(cx, move |window, cx| {
+        if let Some(Some(waystart)) = window.root::<Waystart>() {
+            waystart.update(cx, |waystart, cx| {
+                if prompt {
+                    waystart.prompt_password(network, window, cx)
+                } else {
+                    waystart.connect(network, window, cx)
+                }
+            });
+        }
+    });
+}
+
+/// Keeps the list of networks up to date while the window is open.
+pub fn scan_while_open(window: WindowHandle<Waystart>, cx: &mut App) {
+    cx.spawn(async move |cx| {
+        loop {
+            // The manager connects to NetworkManager asynchronously at startup
+            let wifi = cx.update(|cx| cx.try_global::<WifiManager>().cloned());
 
-        Some(
-            self.entries
-                .iter()
-                .filter_map(|entry| {
-                    search_pattern
-                        .score(
-                            nucleo_matcher::Utf32String::from(entry.network.ssid.clone()).slice(..),
-                            matcher,
-                        )
-                        .map(|score| entry.clone() as Rc<dyn Entry>)
+            let mut changed = false;
+            if let Some(ref wifi) = wifi {
+                let enabled = wifi.enabled().await;
+                *WIFI_ENABLED.lock().unwrap() = Some(enabled);
+                let networks = Arc::<[WifiNetwork]>::from(wifi.networks().await);
+                let mut current = WIFI_NETWORKS.lock().unwrap();
+                changed = summary(&current) != summary(&networks);
+                *current = networks;
+            }
+
+            let is_open = window
+                .update(cx, |waystart, _, cx| {
+                    if changed {
+                        waystart.refresh_results(cx);
+                    }
                 })
-                .collect(),
-        )
+                .is_ok();
+            if !is_open {
+                break;
+            }
+
+            if let Some(wifi) = wifi {
+                wifi.scan().await;
+                cx.background_executor().timer(SCAN_INTERVAL).await;
+            } else {
+                cx.background_executor().timer(Duration::from_secs(1)).await;
+            }
+        }
+    })
+    .detach();
+}
+
+/// A network that matched the search term, before it is ranked.
+struct Candidate {
+    known: bool,
+    score: u32,
+    strength: u8,
+}
+
+/// Returns the scores of the candidates, raised so they sort by whether the user already
+/// connected to the network, then by match score and signal strength.
+fn rank(candidates: &[Candidate]) -> Vec<u32> {
+    let mut order = (0..candidates.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| {
+        let candidate = &candidates[i];
+        Reverse((candidate.known, candidate.score, candidate.strength))
+    });
+
+    let mut scores = vec![0; candidates.len()];
+    let mut floor = 0;
+    for &i in order.iter().rev() {
+        scores[i] = candidates[i].score.max(floor);
+        floor = scores[i] + 1;
+    }
+    scores
+}
+
+fn summary(networks: &[WifiNetwork]) -> Vec<(&str, bool, bool)> {
+    networks
+        .iter()
+        .map(|n| (n.network.ssid.as_str(), n.known, n.connected))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Candidate, rank};
+
+    fn candidate(known: bool, score: u32, strength: u8) -> Candidate {
+        Candidate {
+            known,
+            score,
+            strength,
+        }
+    }
+
+    #[test]
+    fn ranks_known_networks_first() {
+        let scores = rank(&[candidate(false, 120, 90), candidate(true, 100, 40)]);
+
+        assert_eq!(scores[0], 120);
+        assert!(scores[1] > scores[0]);
+    }
+
+    #[test]
+    fn breaks_ties_on_signal_strength() {
+        let scores = rank(&[candidate(false, 100, 30), candidate(false, 100, 70)]);
+
+        assert!(scores[1] > scores[0]);
+    }
+
+    #[test]
+    fn keeps_the_scores_already_in_order() {
+        assert_eq!(
+            rank(&[candidate(true, 80, 50), candidate(true, 150, 50)]),
+            [80, 150]
+        );
     }
 }
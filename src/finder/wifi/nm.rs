@@ -1,45 +1,65 @@
-use gpui::{AppContext, AsyncApp, Global};
+use gpui::Global;
+
+use crate::finder::wifi::WifiNetwork;
 
 #[derive(Clone)]
 pub struct WifiManager {
     nm: nmrs::NetworkManager,
-    connect_tx: smol::channel::Sender<(nmrs::Network, nmrs::WifiSecurity)>,
 }
 
 impl WifiManager {
-    pub async fn new(cx: &mut AsyncApp) -> nmrs::Result<Self> {
-        let nm = nmrs::NetworkManager::new().await?;
-        let (tx, rx) = smol::channel::bounded::<(nmrs::Network, nmrs::WifiSecurity)>(1);
-
-        cx.background_spawn({
-            let nm = nm.clone();
-            async move {
-                while let Ok((network, security)) = rx.recv().await {
-                    nm.connect(&network.ssid, security).await.ok();
-                }
-            }
+    pub async fn new() -> nmrs::Result<Self> {
+        Ok(Self {
+            nm: nmrs::NetworkManager::new().await?,
         })
-        .detach();
+    }
 
-        Ok(Self { nm, connect_tx: tx })
+    pub async fn enabled(&self) -> bool {
+        self.nm.wifi_enabled().await.unwrap_or_default()
     }
 
-    pub fn enabled(&self) -> bool {
-        smol::block_on(self.nm.wifi_enabled()).unwrap_or_default()
+    pub async fn enable(&self, state: bool) -> nmrs::Result<()> {
+        self.nm.set_wifi_enabled(state).await
     }
 
-    pub fn enable(&self, state: bool) -> nmrs::Result<()> {
-        smol::block_on(self.nm.set_wifi_enabled(state))
+    /// Lists the visible networks, keeping the strongest access point of each SSID.
+    pub async fn networks(&self) -> Vec<WifiNetwork> {
+        let mut networks = self.nm.list_networks().await.unwrap_or_default();
+        let saved = self.nm.list_saved_connections().await.unwrap_or_default();
+        let current = self.nm.current_ssid().await;
+
+        networks.retain(|network| !network.ssid.is_empty());
+        networks.sort_by_key(|network| std::cmp::Reverse(network.strength));
+        let mut seen = std::collections::HashSet::new();
+        networks.retain(|network| seen.insert(network.ssid.clone()));
+
+        networks
+            .into_iter()
+            .map(|network| {
+                let connected = current.as_ref() == Some(&network.ssid);
+                WifiNetwork {
+                    known: connected || saved.contains(&network.ssid),
+                    connected,
+                    network,
+                }
+            })
+            .collect()
     }
 
-    pub fn list(&self) -> Vec<nmrs::Network> {
-        smol::block_on(self.nm.list_networks()).unwrap_or_default()
+    /// Asks NetworkManager to scan for networks, the results are available on the next listing.
+    pub async fn scan(&self) {
+        if let Err(e) = self.nm.scan_networks().await {
+            eprintln!("Failed to scan Wi-Fi networks: {}", e);
+        }
     }
 
-    pub fn connect(&self, network: &nmrs::Network, security: nmrs::WifiSecurity) {
-        self.connect_tx
-            .try_send((network.clone(), security))
-            .unwrap();
+    /// Connects to `network`, resolving once NetworkManager activated or rejected the connection.
+    pub async fn connect(
+        &self,
+        network: &nmrs::Network,
+        security: nmrs::WifiSecurity,
+    ) -> nmrs::Result<()> {
+        self.nm.connect(&network.ssid, security).await
     }
 }
 
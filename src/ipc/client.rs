@@ -1,40 +1,85 @@
-use std::cell::RefCell;
-use std::io::Write;
+use std::cell::{Cell, RefCell};
+use std::io::{BufRead, BufReader, Write};
 use std::os::unix::net::UnixStream;
 
-use crate::ipc::{MESSAGE_CLOSE, MESSAGE_OPEN, MESSAGE_QUIT, MESSAGE_TOGGLE, SOCKET_PATH};
+use crate::ipc::protocol::{
+    Event, PROTOCOL_VERSION, Request, Response, ResponseBody, SocketMessage,
+};
+use crate::ipc::socket_path;
 
 pub struct SocketClient {
-    stream: RefCell<UnixStream>,
+    stream: RefCell<BufReader<UnixStream>>,
+    next_id: Cell<u64>,
 }
 
 impl SocketClient {
     pub fn connect() -> Self {
-        Self::try_connect().expect("Failed to bind IPC socket")
+        Self::try_connect().expect("Failed to connect to the IPC socket")
     }
 
+    /// Connects to the daemon and performs the protocol handshake.
     pub fn try_connect() -> std::io::Result<Self> {
-        Ok(Self {
-            stream: RefCell::new(UnixStream::connect(SOCKET_PATH)?),
-        })
+        let client = Self {
+            stream: RefCell::new(BufReader::new(UnixStream::connect(socket_path())?)),
+            next_id: Cell::new(0),
+        };
+
+        match client.send(SocketMessage::Hello {
+            version: PROTOCOL_VERSION,
+        })? {
+            ResponseBody::Hello { version } if version == PROTOCOL_VERSION => Ok(client),
+            _ => Err(std::io::Error::other("Unexpected handshake response")),
+        }
     }
 
-    pub fn send_message_socket(&self, message: SocketMessage) {
+    /// Waits for the next event, once subscribed with [`SocketMessage::Subscribe`].
+    pub fn next_event(&self) -> std::io::Result<Event> {
         let mut stream = self.stream.borrow_mut();
-        if let Err(e) = match message {
-            SocketMessage::Open => stream.write_all(MESSAGE_OPEN),
-            SocketMessage::Close => stream.write_all(MESSAGE_CLOSE),
-            SocketMessage::Toggle => stream.write_all(MESSAGE_TOGGLE),
-            SocketMessage::Quit => stream.write_all(MESSAGE_QUIT),
-        } {
-            eprintln!("Failed to send IPC message: {}", e);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if stream.read_line(&mut line)? == 0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "The daemon closed the connection",
+                ));
+            }
+
+            if let ResponseBody::Event(event) = serde_json::from_str::<Response>(&line)?.body {
+                return Ok(event);
+            }
         }
     }
-}
 
-pub enum SocketMessage {
-    Open,
-    Close,
-    Toggle,
-    Quit,
+    /// Sends a request and waits for the daemon's reply.
+    /// Errors reported by the daemon are returned as [`std::io::Error`]s.
+    pub fn send(&self, message: SocketMessage) -> std::io::Result<ResponseBody> {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+
+        let mut stream = self.stream.borrow_mut();
+        let mut line = serde_json::to_string(&Request { id, message })?;
+        line.push('\n');
+        stream.get_mut().write_all(line.as_bytes())?;
+
+        loop {
+            line.clear();
+            if stream.read_line(&mut line)? == 0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "The daemon closed the connection",
+                ));
+            }
+
+            let response: Response = serde_json::from_str(&line)?;
+            if response.id.is_some_and(|response_id| response_id != id) {
+                continue;
+            }
+
+            return match response.body {
+                ResponseBody::Error { message, .. } => Err(std::io::Error::other(message)),
+                body => Ok(body),
+            };
+        }
+    }
 }
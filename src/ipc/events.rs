@@ -0,0 +1,26 @@
+use gpui::{App, Global};
+
+use crate::ipc::protocol::Event;
+
+/// The channels of the clients listening for events.
+#[derive(Default)]
+pub struct Subscribers {
+    senders: Vec<smol::channel::Sender<Event>>,
+}
+
+impl Global for Subscribers {}
+
+impl Subscribers {
+    pub fn subscribe(&mut self) -> smol::channel::Receiver<Event> {
+        let (sender, receiver) = smol::channel::unbounded();
+        self.senders.push(sender);
+        receiver
+    }
+}
+
+/// Sends `event` to every subscriber, forgetting the ones that went away.
+pub fn emit(event: Event, cx: &mut App) {
+    cx.default_global::<Subscribers>()
+        .senders
+        .retain(|sender| sender.try_send(event.clone()).is_ok());
+}
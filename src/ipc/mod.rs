@@ -1,9 +1,41 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
 pub mod client;
+pub mod events;
+pub mod protocol;
 pub mod server;
 
-const SOCKET_PATH: &str = "/tmp/waystart.sock";
+static SOCKET_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Overrides the IPC socket path, for example to talk to a daemon running in a nested compositor.
+/// Must be called before the socket is used.
+pub fn set_socket_path(path: PathBuf) {
+    let _ = SOCKET_PATH.set(path);
+}
+
+/// Path of the IPC socket.
+/// Defaults to `$WAYSTART_SOCKET`, or to a socket per Wayland display in the user's runtime dir.
+pub fn socket_path() -> &'static Path {
+    SOCKET_PATH.get_or_init(|| {
+        if let Some(path) = std::env::var_os("WAYSTART_SOCKET") {
+            return PathBuf::from(path);
+        }
+
+        // `WAYLAND_DISPLAY` can also be an absolute path to the compositor's socket.
+        let display = std::env::var_os("WAYLAND_DISPLAY")
+            .as_deref()
+            .and_then(|display| Path::new(display).file_name())
+            .map(|display| display.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "wayland-0".to_string());
 
-const MESSAGE_OPEN: &[u8] = b"open";
-const MESSAGE_CLOSE: &[u8] = b"close";
-const MESSAGE_TOGGLE: &[u8] = b"toggle";
-const MESSAGE_QUIT: &[u8] = b"quit";
+        match dirs::runtime_dir() {
+            Some(dir) => dir.join(format!("waystart-{}.sock", display)),
+            None => std::env::temp_dir().join(format!(
+                "waystart-{}-{}.sock",
+                rustix::process::getuid().as_raw(),
+                display
+            )),
+        }
+    })
+}
@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+
+/// Version of the line-delimited JSON protocol spoken over the IPC socket.
+/// Clients must announce it with a [`SocketMessage::Hello`] before sending any other request.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// A command understood by the daemon.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum SocketMessage {
+    Hello {
+        version: u32,
+    },
+    Open(OpenOptions),
+    Close,
+    Toggle(OpenOptions),
+    /// Lets the user pick one of the items, replied to with [`ResponseBody::Selected`] or [`ResponseBody::Cancelled`].
+    Dmenu(DmenuOptions),
+    /// Searches without opening the window, replied to with [`ResponseBody::Results`].
+    Query(QueryOptions),
+    /// Rebuilds the desktop index and reloads the config and favorites, replied to with [`ResponseBody::Reloaded`].
+    Reload,
+    /// Keeps the connection open to stream [`ResponseBody::Event`]s, after replying with [`ResponseBody::Ok`].
+    Subscribe,
+    Quit,
+}
+
+/// How to present the window when opening it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OpenOptions {
+    /// Output to open the window on, see [`crate::config::WindowOutput`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Search term to prefill the search bar with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// Name of the only finder to show results from, such as `wifi`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+}
+
+impl SocketMessage {
+    /// Parses one of the legacy plain-text commands, such as `open` or `toggle DP-1`.
+    pub fn from_legacy(line: &str) -> Option<Self> {
+        let (command, output) = match line.split_once(' ') {
+            Some((command, output)) => (command, Some(output.to_string())),
+            None => (line, None),
+        };
+        let options = OpenOptions {
+            output,
+            ..Default::default()
+        };
+
+        match command {
+            "open" => Some(Self::Open(options)),
+            "close" => Some(Self::Close),
+            "toggle" => Some(Self::Toggle(options)),
+            "quit" => Some(Self::Quit),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DmenuOptions {
+    pub items: Vec<String>,
+    /// Allow choosing the search term itself when it isn't one of the items.
+    #[serde(default)]
+    pub print_query: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct QueryOptions {
+    pub search_term: String,
+    /// Name of the only finder to search, such as `desktop`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Maximum number of results.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueryResult {
+    pub id: String,
+    pub text: String,
+    pub description: Option<String>,
+    pub score: u32,
+    pub finder: String,
+}
+
+/// What changed when reloading.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReloadReport {
+    /// Ids of the entries that appeared.
+    pub added: Vec<String>,
+    /// Ids of the entries that disappeared.
+    pub removed: Vec<String>,
+    pub config_changed: bool,
+    pub favorites_changed: bool,
+}
+
+/// A single line sent by a client.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Request {
+    pub id: u64,
+    #[serde(flatten)]
+    pub message: SocketMessage,
+}
+
+/// A single line sent back by the daemon, answering the request with the same `id`.
+/// The `id` is missing when the request was too malformed to read it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Response {
+    pub id: Option<u64>,
+    #[serde(flatten)]
+    pub body: ResponseBody,
+}
+
+impl Response {
+    pub fn ok(id: u64) -> Self {
+        Self {
+            id: Some(id),
+            body: ResponseBody::Ok,
+        }
+    }
+
+    pub fn error(id: Option<u64>, code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            id,
+            body: ResponseBody::Error {
+                code,
+                message: message.into(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ResponseBody {
+    Hello { version: u32 },
+    Ok,
+    Selected { text: String },
+    Cancelled,
+    Results { results: Vec<QueryResult> },
+    Reloaded(ReloadReport),
+    Event(Event),
+    Error { code: ErrorCode, message: String },
+}
+
+/// Something that happened in the daemon, streamed to subscribers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    WindowOpened,
+    WindowClosed,
+    QueryChanged { query: String },
+    // Entries are named `entry` rather than `id`, which is taken by the response
+    EntryExecuted { entry: String, finder: String },
+    FavoriteToggled { entry: String, favorite: bool },
+    ConfigReloaded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCode {
+    /// The line isn't a valid request.
+    InvalidRequest,
+    /// A request was sent before the `hello` handshake.
+    HandshakeRequired,
+    /// The client speaks another version of the protocol.
+    UnsupportedVersion,
+    /// The daemon failed to carry out the request.
+    Failed,
+}
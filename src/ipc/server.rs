@@ -1,15 +1,59 @@
 use std::cell::RefCell;
+use std::fs::File;
+use std::os::fd::FromRawFd;
+use std::os::unix::fs::PermissionsExt;
 use std::rc::Rc;
 
-use smol::io::{AsyncBufReadExt, BufReader};
+use serde::Deserialize;
+use smol::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use smol::net::unix::{UnixListener, UnixStream};
 use smol::stream::StreamExt;
 
 use gpui::{AsyncApp, Entity, WindowHandle};
 
-use crate::ipc::*;
-use crate::open_window;
+use crate::finder::Finders;
+use crate::finder::dmenu;
+use crate::finder::hidden::Hidden;
+use crate::ipc::events::Subscribers;
+use crate::ipc::protocol::{
+    ErrorCode, OpenOptions, PROTOCOL_VERSION, Request, Response, ResponseBody, SocketMessage,
+};
+use crate::ipc::socket_path;
+use crate::query;
 use crate::ui::Waystart;
+use crate::{open_window, reload, reset_search};
+
+/// First file descriptor passed by systemd socket activation.
+const SD_LISTEN_FDS_START: i32 = 3;
+
+/// Locks the daemon's lock file, so only one daemon runs per socket.
+/// The lock is released once the returned file is dropped.
+pub fn lock_daemon() -> Result<File, String> {
+    let path = socket_path().with_extension("lock");
+    let file = File::create(&path)
+        .map_err(|e| format!("Failed to create {}: {}", path.to_string_lossy(), e))?;
+    rustix::fs::flock(&file, rustix::fs::FlockOperation::NonBlockingLockExclusive)
+        .map_err(|_| "Another Waystart daemon is already running".to_string())?;
+    Ok(file)
+}
+
+/// Takes the socket passed by systemd socket activation, if any.
+fn activated_listener() -> Option<UnixListener> {
+    let pid = std::env::var("LISTEN_PID").ok()?.parse::<u32>().ok()?;
+    let fds = std::env::var("LISTEN_FDS").ok()?.parse::<u32>().ok()?;
+    if pid != std::process::id() || fds < 1 {
+        return None;
+    }
+
+    // SAFETY: systemd hands the sockets over to us, starting at SD_LISTEN_FDS_START
+    let listener = unsafe { std::os::unix::net::UnixListener::from_raw_fd(SD_LISTEN_FDS_START) };
+    // Keep the apps we launch from inheriting the socket
+    rustix::io::fcntl_setfd(&listener, rustix::io::FdFlags::CLOEXEC).ok();
+
+    UnixListener::try_from(listener)
+        .inspect_err(|e| eprintln!("Failed to use the activated IPC socket: {}", e))
+        .ok()
+}
 
 #[derive(Clone)]
 pub struct SocketServer {
@@ -27,12 +71,21 @@ impl SocketServer {
         }
     }
 
+    /// Accepts connections on the socket passed by systemd, or on a new one at [`socket_path`].
+    /// The caller must hold the [`lock_daemon`] lock, as any existing socket is replaced.
     pub fn listen(&self) {
-        if std::fs::exists(SOCKET_PATH).ok().unwrap_or(false) {
-            std::fs::remove_file(SOCKET_PATH).expect("Failed to remove existing IPC socket");
-        }
+        let listener = activated_listener().unwrap_or_else(|| {
+            let path = socket_path();
+            if std::fs::exists(path).ok().unwrap_or(false) {
+                std::fs::remove_file(path).expect("Failed to remove existing IPC socket");
+            }
 
-        let listener = UnixListener::bind(SOCKET_PATH).expect("Failed to bind IPC socket");
+            let listener = UnixListener::bind(path).expect("Failed to bind IPC socket");
+            if let Err(e) = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)) {
+                eprintln!("Failed to restrict the IPC socket permissions: {}", e);
+            }
+            listener
+        });
 
         let this = self.clone();
         self.app
@@ -56,50 +109,232 @@ impl SocketServer {
             .detach();
     }
 
+    /// Carries out a message received through another channel than the socket, such as D-Bus.
+    pub async fn handle(
+        &self,
+        message: SocketMessage,
+        cx: &mut AsyncApp,
+    ) -> Result<ResponseBody, String> {
+        Self::run(message, &self.window, &self.waystart, cx).await
+    }
+
     async fn handle_ipc_stream(
         stream: UnixStream,
         window: Rc<RefCell<Option<WindowHandle<Waystart>>>>,
         waystart: Entity<Waystart>,
         cx: &mut AsyncApp,
     ) {
+        if !Self::is_same_user(&stream) {
+            eprintln!("Rejected an IPC connection from another user");
+            return;
+        }
+
+        let mut writer = stream.clone();
         let reader = BufReader::new(stream);
         let mut lines = reader.lines();
+        let mut handshake = false;
 
-        while let Some(Ok(message)) = lines.next().await {
-            match message.as_bytes() {
-                MESSAGE_OPEN => cx.update(|cx| {
-                    let mut window = window.borrow_mut();
-                    if window.map(|w| w.is_active(cx).is_none()).unwrap_or(true) {
-                        *window = Some(open_window(cx, waystart.clone()));
+        while let Some(Ok(line)) = lines.next().await {
+            // Plain-text commands predate the JSON protocol and never get a reply.
+            if !line.starts_with('{') {
+                let Some(message) = SocketMessage::from_legacy(&line) else {
+                    eprintln!("Received unknown IPC message: {}", line);
+                    return;
+                };
+                if let Err(e) = Self::run(message, &window, &waystart, cx).await {
+                    eprintln!("Failed to handle IPC message: {}", e);
+                }
+                continue;
+            }
+
+            let response = match Self::parse_request(&line) {
+                Err(response) => response,
+                Ok(Request {
+                    id,
+                    message: SocketMessage::Hello { version },
+                }) => {
+                    if version == PROTOCOL_VERSION {
+                        handshake = true;
+                        Response {
+                            id: Some(id),
+                            body: ResponseBody::Hello {
+                                version: PROTOCOL_VERSION,
+                            },
+                        }
+                    } else {
+                        Response::error(
+                            Some(id),
+                            ErrorCode::UnsupportedVersion,
+                            format!(
+                                "Unsupported protocol version {}, expected {}",
+                                version, PROTOCOL_VERSION
+                            ),
+                        )
                     }
-                }),
-                MESSAGE_CLOSE => cx.update(|cx| {
-                    let mut window = window.borrow_mut();
-                    if let Some(window) = window.take()
-                        && window.is_active(cx).is_some()
-                    {
-                        window
-                            .update(cx, |_, window, _| window.remove_window())
-                            .unwrap();
+                }
+                Ok(Request { id, .. }) if !handshake => Response::error(
+                    Some(id),
+                    ErrorCode::HandshakeRequired,
+                    "Expected a hello request first",
+                ),
+                Ok(Request {
+                    id,
+                    message: SocketMessage::Quit,
+                }) => {
+                    // Reply before quitting, since the connection won't outlive the app.
+                    if let Err(e) = Self::write_response(&mut writer, &Response::ok(id)).await {
+                        eprintln!("Failed to reply to IPC message: {}", e);
                     }
-                }),
-                MESSAGE_TOGGLE => cx.update(|cx| {
-                    let mut window = window.borrow_mut();
-                    if let Some(window) = window.take()
-                        && window.is_active(cx).is_some()
-                    {
-                        window
-                            .update(cx, |_, window, _| window.remove_window())
-                            .unwrap();
-                    } else {
-                        *window = Some(open_window(cx, waystart.clone()));
+                    cx.update(|cx| cx.quit());
+                    return;
+                }
+                Ok(Request {
+                    id,
+                    message: SocketMessage::Subscribe,
+                }) => {
+                    let events = cx.update(|cx| cx.default_global::<Subscribers>().subscribe());
+                    if let Err(e) = Self::write_response(&mut writer, &Response::ok(id)).await {
+                        eprintln!("Failed to reply to IPC message: {}", e);
+                        return;
+                    }
+
+                    // The connection is dedicated to events from now on
+                    while let Ok(event) = events.recv().await {
+                        let response = Response {
+                            id: Some(id),
+                            body: ResponseBody::Event(event),
+                        };
+                        if Self::write_response(&mut writer, &response).await.is_err() {
+                            return;
+                        }
                     }
-                }),
-                MESSAGE_QUIT => cx.update(|cx| cx.quit()),
-                _ => {
-                    eprintln!("Received unknown IPC message: {}", message);
                     return;
                 }
+                Ok(Request {
+                    id,
+                    message: SocketMessage::Dmenu(options),
+                }) => {
+                    // Replace whatever the window is showing with the session
+                    let closed = Self::run(SocketMessage::Close, &window, &waystart, cx).await;
+                    let choice = dmenu::start(options.items, options.print_query);
+                    let open = SocketMessage::Open(OpenOptions {
+                        output: options.output,
+                        query: None,
+                        mode: Some(dmenu::NAME.to_string()),
+                    });
+
+                    let result = match closed {
+                        Ok(_) => Self::run(open, &window, &waystart, cx).await,
+                        Err(e) => Err(e),
+                    };
+
+                    match result {
+                        Ok(_) => Response {
+                            id: Some(id),
+                            body: match choice.recv().await {
+                                Ok(Some(text)) => ResponseBody::Selected { text },
+                                _ => ResponseBody::Cancelled,
+                            },
+                        },
+                        Err(e) => {
+                            dmenu::finish(None);
+                            Response::error(Some(id), ErrorCode::Failed, e)
+                        }
+                    }
+                }
+                Ok(Request { id, message }) => {
+                    match Self::run(message, &window, &waystart, cx).await {
+                        Ok(body) => Response { id: Some(id), body },
+                        Err(e) => Response::error(Some(id), ErrorCode::Failed, e),
+                    }
+                }
+            };
+
+            if let Err(e) = Self::write_response(&mut writer, &response).await {
+                eprintln!("Failed to reply to IPC message: {}", e);
+                return;
+            }
+        }
+    }
+
+    fn is_same_user(stream: &UnixStream) -> bool {
+        match rustix::net::sockopt::socket_peercred(stream) {
+            Ok(credentials) => credentials.uid == rustix::process::getuid(),
+            Err(e) => {
+                eprintln!("Failed to read the IPC peer credentials: {}", e);
+                false
+            }
+        }
+    }
+
+    fn parse_request(line: &str) -> Result<Request, Response> {
+        let value: serde_json::Value = serde_json::from_str(line)
+            .map_err(|e| Response::error(None, ErrorCode::InvalidRequest, e.to_string()))?;
+        let id = value.get("id").and_then(serde_json::Value::as_u64);
+
+        Request::deserialize(value)
+            .map_err(|e| Response::error(id, ErrorCode::InvalidRequest, e.to_string()))
+    }
+
+    async fn write_response(writer: &mut UnixStream, response: &Response) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(response)?;
+        line.push('\n');
+        writer.write_all(line.as_bytes()).await
+    }
+
+    async fn run(
+        message: SocketMessage,
+        window: &RefCell<Option<WindowHandle<Waystart>>>,
+        waystart: &Entity<Waystart>,
+        cx: &mut AsyncApp,
+    ) -> Result<ResponseBody, String> {
+        match message {
+            SocketMessage::Hello { .. } | SocketMessage::Dmenu(_) | SocketMessage::Subscribe => {
+                Err("Not supported by this command".to_string())
+            }
+            SocketMessage::Open(options) => cx.update(|cx| {
+                let mut window = window.borrow_mut();
+                if window.map(|w| w.is_active(cx).is_none()).unwrap_or(true) {
+                    *window = Some(open_window(cx, waystart.clone(), options)?);
+                } else if options.query.is_some() || options.mode.is_some() {
+                    // Without either, the open window is left as it is
+                    reset_search(cx, waystart, &options)?;
+                }
+                Ok(ResponseBody::Ok)
+            }),
+            SocketMessage::Close => cx.update(|cx| {
+                let mut window = window.borrow_mut();
+                if let Some(window) = window.take()
+                    && window.is_active(cx).is_some()
+                {
+                    window
+                        .update(cx, |_, window, _| window.remove_window())
+                        .map_err(|e| format!("Failed to close the window: {}", e))?;
+                }
+                Ok(ResponseBody::Ok)
+            }),
+            SocketMessage::Toggle(options) => cx.update(|cx| {
+                let mut window = window.borrow_mut();
+                if let Some(window) = window.take()
+                    && window.is_active(cx).is_some()
+                {
+                    window
+                        .update(cx, |_, window, _| window.remove_window())
+                        .map_err(|e| format!("Failed to close the window: {}", e))?;
+                } else {
+                    *window = Some(open_window(cx, waystart.clone(), options)?);
+                }
+                Ok(ResponseBody::Ok)
+            }),
+            SocketMessage::Query(options) => cx.update(|cx| {
+                let results =
+                    query::results(&options, cx.global::<Finders>(), cx.global::<Hidden>())?;
+                Ok(ResponseBody::Results { results })
+            }),
+            SocketMessage::Reload => Ok(ResponseBody::Reloaded(reload(cx).await)),
+            SocketMessage::Quit => {
+                cx.update(|cx| cx.quit());
+                Ok(ResponseBody::Ok)
             }
         }
     }
@@ -1,36 +1,160 @@
 use gpui::{
-    App, AppContext, Application, Bounds, Entity, Focusable, QuitMode, TitlebarOptions,
+    App, AppContext, Application, AsyncApp, Bounds, Entity, Focusable, QuitMode, TitlebarOptions,
     WindowBounds, WindowDecorations, WindowHandle, WindowKind, WindowOptions, point, px, size,
 };
 
-use crate::config::Config;
+use crate::config::{Config, WindowMode, WindowOutput};
 use crate::finder::Finders;
 use crate::finder::desktop::frequency::DESKTOP_FREQUENCIES;
+use crate::finder::dmenu;
 use crate::finder::favorites::Favorites;
+use crate::finder::hidden::Hidden;
+use crate::finder::saves::Saves;
 use crate::finder::wifi::WifiManager;
-use crate::ipc::client::{SocketClient, SocketMessage};
+use crate::ipc::client::SocketClient;
+use crate::ipc::events;
+use crate::ipc::protocol::{
+    DmenuOptions, Event, OpenOptions, QueryOptions, ReloadReport, ResponseBody, SocketMessage,
+};
 use crate::ipc::server::SocketServer;
 use crate::quick_access::Quicks;
 use crate::ui::Waystart;
 
 mod cli;
 mod config;
+mod dbus;
 mod finder;
 mod ipc;
+mod migration;
+mod output;
+mod query;
 mod quick_access;
 mod ui;
+mod watcher;
 
 fn main() {
-    let message = match cli::Waystart::from_env_or_exit().subcommand {
-        cli::WaystartCmd::Open(_) => SocketMessage::Open,
+    let args = cli::Waystart::from_env_or_exit();
+    if let Some(socket) = args.socket {
+        ipc::set_socket_path(socket);
+    }
+
+    let message = match args.subcommand {
+        cli::WaystartCmd::Open(options) => SocketMessage::Open(OpenOptions {
+            output: options.output,
+            query: options.query,
+            mode: options.mode,
+        }),
         cli::WaystartCmd::Close(_) => SocketMessage::Close,
-        cli::WaystartCmd::Toggle(_) => SocketMessage::Toggle,
-        cli::WaystartCmd::Standalone(_) => {
+        cli::WaystartCmd::Toggle(options) => SocketMessage::Toggle(OpenOptions {
+            output: options.output,
+            query: options.query,
+            mode: options.mode,
+        }),
+        cli::WaystartCmd::Dmenu(options) => {
+            let options = DmenuOptions {
+                items: std::io::stdin().lines().map_while(Result::ok).collect(),
+                print_query: options.print_query,
+                output: options.output,
+            };
+            let choice = match SocketClient::try_connect() {
+                Ok(client) => match client.send(SocketMessage::Dmenu(options)) {
+                    Ok(ResponseBody::Selected { text }) => Some(text),
+                    Ok(_) => None,
+                    Err(e) => {
+                        eprintln!("Failed to send IPC message: {}", e);
+                        None
+                    }
+                },
+                Err(_) => {
+                    let choice = dmenu::start(options.items, options.print_query);
+                    create_app(
+                        false,
+                        OpenOptions {
+                            output: options.output,
+                            query: None,
+                            mode: Some(dmenu::NAME.to_string()),
+                        },
+                    );
+                    // The app only quits once the window is closed, which ends the session
+                    choice.try_recv().ok().flatten()
+                }
+            };
+
+            match choice {
+                Some(text) => println!("{}", text),
+                None => std::process::exit(1),
+            }
+            return;
+        }
+        cli::WaystartCmd::Reload(_) => {
+            let client = SocketClient::connect();
+            match client.send(SocketMessage::Reload) {
+                Ok(ResponseBody::Reloaded(report)) => print_reload_report(&report),
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("Failed to send IPC message: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        cli::WaystartCmd::Unhide(options) => {
+            let mut hidden = Hidden::load();
+            if !hidden.remove(options.id.as_str()) {
+                eprintln!("{} is not hidden", options.id);
+                std::process::exit(1);
+            }
+            smol::block_on(hidden.save());
+
+            // The daemon keeps the hidden entries in memory until it reloads them
             if let Ok(client) = SocketClient::try_connect() {
-                client.send_message_socket(SocketMessage::Open);
+                send_or_exit(&client, SocketMessage::Reload);
+            }
+            return;
+        }
+        cli::WaystartCmd::Subscribe(_) => {
+            let client = SocketClient::connect();
+            send_or_exit(&client, SocketMessage::Subscribe);
+            loop {
+                match client.next_event() {
+                    Ok(event) => println!(
+                        "{}",
+                        serde_json::to_string(&event).expect("Failed to serialize event")
+                    ),
+                    Err(e) => {
+                        eprintln!("Failed to receive IPC event: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        cli::WaystartCmd::Query(options) => {
+            let json = options.json;
+            let options = QueryOptions {
+                search_term: options.search_term,
+                mode: options.mode,
+                limit: options.limit,
+            };
+            let success = query::run(&options, json);
+            std::process::exit(if success { 0 } else { 1 });
+        }
+        cli::WaystartCmd::Config(cmd) => match cmd.subcommand {
+            cli::ConfigCmd::Check(check) => {
+                let valid = Config::check(check.path.as_deref());
+                std::process::exit(if valid { 0 } else { 1 });
+            }
+        },
+        cli::WaystartCmd::Standalone(options) => {
+            let options = OpenOptions {
+                output: options.output,
+                query: options.query,
+                mode: options.mode,
+            };
+            if let Ok(client) = SocketClient::try_connect() {
+                send_or_exit(&client, SocketMessage::Open(options));
                 return;
             } else {
-                create_app(false);
+                create_app(false, options);
                 return;
             }
         }
@@ -38,17 +162,56 @@ fn main() {
             if options.exit {
                 SocketMessage::Quit
             } else {
-                create_app(true);
+                // Held until the daemon exits
+                let _lock = match ipc::server::lock_daemon() {
+                    Ok(lock) => lock,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        std::process::exit(1);
+                    }
+                };
+                create_app(true, OpenOptions::default());
                 return;
             }
         }
     };
 
     let client = SocketClient::connect();
-    client.send_message_socket(message);
+    send_or_exit(&client, message);
+}
+
+fn print_reload_report(report: &ReloadReport) {
+    for id in &report.added {
+        println!("Added {}", id);
+    }
+    for id in &report.removed {
+        println!("Removed {}", id);
+    }
+    if report.config_changed {
+        println!("Reloaded the config");
+    }
+    if report.favorites_changed {
+        println!("Reloaded the favorites");
+    }
+    if report.added.is_empty()
+        && report.removed.is_empty()
+        && !report.config_changed
+        && !report.favorites_changed
+    {
+        println!("Nothing changed");
+    }
 }
 
-fn create_app(daemon: bool) {
+fn send_or_exit(client: &SocketClient, message: SocketMessage) {
+    if let Err(e) = client.send(message) {
+        eprintln!("Failed to send IPC message: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn create_app(daemon: bool, options: OpenOptions) {
+    migration::migrate();
+
     Application::new()
         .with_assets(ui::Assets)
         .with_quit_mode(if daemon {
@@ -60,70 +223,141 @@ fn create_app(daemon: bool) {
             ui::init(cx);
             cx.set_global(Config::load());
             cx.set_global(Favorites::load());
+            cx.set_global(Hidden::load());
             cx.set_global(Finders::new());
             cx.set_global(Quicks::new());
 
             cx.spawn(async move |cx| {
-                if let Ok(wifi) = WifiManager::new(cx).await {
+                if let Ok(wifi) = WifiManager::new().await {
                     cx.update(move |cx| cx.set_global(wifi));
                 }
             })
             .detach();
 
+            // Closing the window without choosing anything cancels the dmenu session
+            cx.on_window_closed(|cx| {
+                dmenu::finish(None);
+                events::emit(Event::WindowClosed, cx);
+            })
+            .detach();
+
             cx.on_app_quit(|_| DESKTOP_FREQUENCIES.save()).detach();
             cx.on_app_quit(|cx| {
                 let favorites = cx.remove_global::<Favorites>();
                 async move { favorites.save().await }
             })
             .detach();
+            cx.on_app_quit(|cx| {
+                let hidden = cx.remove_global::<Hidden>();
+                async move { hidden.save().await }
+            })
+            .detach();
 
             let waystart = cx.new(Waystart::new);
 
             if daemon {
+                Config::watch(cx);
+                Finders::watch(cx);
                 let server = SocketServer::new(cx.to_async(), waystart);
                 server.listen();
-            } else {
-                open_window(cx, waystart);
+                dbus::serve(server, cx);
+            } else if let Err(e) = open_window(cx, waystart, options) {
+                eprintln!("Failed to open the window: {}", e);
+                cx.quit();
             }
         });
 }
 
-pub fn open_window(cx: &mut App, waystart: Entity<Waystart>) -> WindowHandle<Waystart> {
-    cx.update_entity(&waystart, |waystart: &mut Waystart, cx| {
-        waystart.reset_search(cx)
+/// Rebuilds the desktop index and reloads the config, favorites and hidden entries from disk.
+pub async fn reload(cx: &mut AsyncApp) -> ReloadReport {
+    // Reading the files back before the pending saves are written would undo the latest changes
+    Saves::wait(cx).await;
+
+    cx.update(|cx| {
+        let (added, removed) = cx.global_mut::<Finders>().reload();
+
+        let config = Config::load();
+        let config_changed = config != *cx.global::<Config>();
+        cx.set_global(config);
+
+        let favorites = Favorites::load();
+        let favorites_changed = *favorites != **cx.global::<Favorites>();
+        cx.set_global(favorites);
+        cx.set_global(Hidden::load());
+
+        if config_changed {
+            events::emit(Event::ConfigReloaded, cx);
+        }
+        cx.refresh_windows();
+
+        ReloadReport {
+            added: added.iter().map(ToString::to_string).collect(),
+            removed: removed.iter().map(ToString::to_string).collect(),
+            config_changed,
+            favorites_changed,
+        }
+    })
+}
+
+/// Restricts the results to the mode of `options` and prefills its query in the search bar.
+pub fn reset_search(
+    cx: &mut App,
+    waystart: &Entity<Waystart>,
+    options: &OpenOptions,
+) -> Result<(), String> {
+    cx.global_mut::<Finders>()
+        .set_mode(options.mode.as_deref())?;
+    cx.update_entity(waystart, |waystart: &mut Waystart, cx| {
+        waystart.reset_search(options.query.as_deref().unwrap_or_default(), cx)
     });
+    Ok(())
+}
 
-    let bounds = Bounds::centered(None, size(px(800.), px(500.)), cx);
-    cx.open_window(
-        WindowOptions {
-            // #[cfg(target_os = "linux")]
-            // kind: WindowKind::LayerShell(gpui::layer_shell::LayerShellOptions {
-            //     namespace: "waystart".to_string(),
-            //     anchor: gpui::layer_shell::Anchor::LEFT
-            //         | gpui::layer_shell::Anchor::RIGHT
-            //         | gpui::layer_shell::Anchor::BOTTOM
-            //         | gpui::layer_shell::Anchor::TOP,
-            //     margin: Some((px(0.), px(0.), px(40.), px(0.))),
-            //     keyboard_interactivity: gpui::layer_shell::KeyboardInteractivity::Exclusive,
-            //     ..Default::default()
-            // }),
-            // #[cfg(not(target_os = "linux"))]
-            kind: WindowKind::PopUp,
-            is_resizable: false,
-            is_minimizable: false,
-            window_bounds: Some(WindowBounds::Windowed(bounds)),
-            window_decorations: Some(WindowDecorations::Client),
-            titlebar: Some(TitlebarOptions {
-                title: Some("Waystart".into()),
-                appears_transparent: true,
-                traffic_light_position: Some(point(px(-100.0), px(0.0))),
-            }),
-            ..Default::default()
-        },
-        |window, cx| {
-            window.focus(&waystart.focus_handle(cx), cx);
-            waystart
-        },
-    )
-    .unwrap()
+pub fn open_window(
+    cx: &mut App,
+    waystart: Entity<Waystart>,
+    options: OpenOptions,
+) -> Result<WindowHandle<Waystart>, String> {
+    reset_search(cx, &waystart, &options)?;
+
+    let output = options.output.map(WindowOutput::from);
+
+    let window_config = &cx.global::<Config>().window;
+    let kind = match window_config.mode {
+        WindowMode::Popup => WindowKind::PopUp,
+        WindowMode::LayerShell => WindowKind::LayerShell(window_config.layer_shell_options()),
+    };
+    let display_id = output::find_display(output.as_ref().unwrap_or(&window_config.output), cx);
+    let bounds = Bounds::centered(
+        display_id,
+        size(px(window_config.width), px(window_config.height)),
+        cx,
+    );
+
+    let window = cx
+        .open_window(
+            WindowOptions {
+                kind,
+                display_id,
+                is_resizable: false,
+                is_minimizable: false,
+                window_bounds: Some(WindowBounds::Windowed(bounds)),
+                window_decorations: Some(WindowDecorations::Client),
+                titlebar: Some(TitlebarOptions {
+                    title: Some("Waystart".into()),
+                    appears_transparent: true,
+                    traffic_light_position: Some(point(px(-100.0), px(0.0))),
+                }),
+                ..Default::default()
+            },
+            |window, cx| {
+                window.focus(&waystart.focus_handle(cx), cx);
+                waystart
+            },
+        )
+        .unwrap();
+
+    finder::wifi::scan_while_open(window, cx);
+    events::emit(Event::WindowOpened, cx);
+    Ok(window)
 }
@@ -0,0 +1,229 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::CONFIG_SAVE_PATH;
+use crate::finder::desktop::frequency::FREQUENCIES_SAVE_PATH;
+use crate::finder::favorites::FAVORITES_SAVE_PATH;
+
+/// Moves the files written by older versions to their XDG locations.
+///
+/// Older versions stored the config and the favorites in the same `waystart.toml` file of the
+/// data directory, and the launch history in the cache directory. The legacy files are kept with
+/// a `.bak` extension once they are migrated.
+pub fn migrate() {
+    let targets = Targets {
+        config: &CONFIG_SAVE_PATH,
+        favorites: &FAVORITES_SAVE_PATH,
+        frequencies: &FREQUENCIES_SAVE_PATH,
+    };
+    migrate_files(
+        dirs::data_dir().map(|dir| dir.join("waystart.toml")),
+        dirs::cache_dir().map(|dir| dir.join("waystart.toml")),
+        &targets,
+    );
+}
+
+/// Where the legacy files are moved to.
+struct Targets<'a> {
+    config: &'a Path,
+    favorites: &'a Path,
+    frequencies: &'a Path,
+}
+
+fn migrate_files(mixed: Option<PathBuf>, frequencies: Option<PathBuf>, targets: &Targets) {
+    if let Some(legacy) = mixed
+        && legacy.is_file()
+    {
+        match split_mixed_file(&legacy, targets) {
+            Ok(()) => backup(&legacy),
+            Err(err) => eprintln!("Failed to migrate {}: {}", legacy.to_string_lossy(), err),
+        }
+    }
+
+    if let Some(legacy) = frequencies
+        && legacy.is_file()
+    {
+        match move_file(&legacy, targets.frequencies) {
+            Ok(()) => backup(&legacy),
+            Err(err) => eprintln!("Failed to migrate {}: {}", legacy.to_string_lossy(), err),
+        }
+    }
+}
+
+/// Moves the favorites out of the legacy file, leaving the rest of it, comments included, as the
+/// config.
+fn split_mixed_file(legacy: &Path, targets: &Targets) -> Result<(), String> {
+    let content = std::fs::read_to_string(legacy).map_err(|e| e.to_string())?;
+    let mut config = content
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| e.to_string())?;
+
+    let mut files = Vec::new();
+    if let Some(favorites) = config.remove("favorites") {
+        let mut document = toml_edit::DocumentMut::new();
+        document.insert("favorites", favorites);
+        files.push((targets.favorites, document.to_string()));
+    }
+    if !config.is_empty() {
+        files.push((targets.config, config.to_string()));
+    }
+
+    // Check every target first, so a conflict doesn't leave the migration half done
+    for (path, content) in &files {
+        check_target(path, content)?;
+    }
+    for (path, content) in &files {
+        write_target(path, content)?;
+    }
+
+    Ok(())
+}
+
+fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    let content = std::fs::read_to_string(from).map_err(|e| e.to_string())?;
+    check_target(to, &content)?;
+    write_target(to, &content)
+}
+
+/// Checks that `path` is missing, or already holds `content` from an interrupted migration.
+fn check_target(path: &Path, content: &str) -> Result<(), String> {
+    match std::fs::read_to_string(path) {
+        Ok(existing) if existing == content => Ok(()),
+        Ok(_) => Err(format!(
+            "{} already exists, merge it by hand",
+            path.to_string_lossy()
+        )),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn write_target(path: &Path, content: &str) -> Result<(), String> {
+    if path.exists() {
+        return Ok(());
+    }
+
+    create_parent_dir(path)?;
+    std::fs::write(path, content).map_err(|e| e.to_string())
+}
+
+fn create_parent_dir(path: &Path) -> Result<(), String> {
+    match path.parent() {
+        Some(dir) => std::fs::create_dir_all(dir).map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+fn backup(legacy: &Path) {
+    if let Err(err) = std::fs::rename(legacy, legacy.with_extension("toml.bak")) {
+        eprintln!("Failed to back up {}: {}", legacy.to_string_lossy(), err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{Targets, migrate_files};
+
+    const LEGACY: &str = concat!(
+        "# Launch apps in foot\n",
+        "terminal = \"foot\"\n",
+        "favorites = [\"firefox\"]\n",
+        "\n",
+        "[window]\n",
+        "width = 600 # wider than the default\n",
+    );
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    fn migrate(dir: &Path) {
+        let config = dir.join("config/config.toml");
+        let favorites = dir.join("state/favorites.toml");
+        let frequencies = dir.join("state/history.toml");
+        migrate_files(
+            Some(dir.join("data/waystart.toml")),
+            Some(dir.join("cache/waystart.toml")),
+            &Targets {
+                config: &config,
+                favorites: &favorites,
+                frequencies: &frequencies,
+            },
+        );
+    }
+
+    fn legacy_dir() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in [
+            ("data/waystart.toml", LEGACY),
+            ("cache/waystart.toml", "[firefox]\nscore = 1\n"),
+        ] {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn splits_the_mixed_file_keeping_comments() {
+        let dir = legacy_dir();
+        migrate(dir.path());
+
+        let config = read(&dir.path().join("config/config.toml"));
+        assert!(config.contains("# Launch apps in foot"));
+        assert!(config.contains("width = 600 # wider than the default"));
+        assert!(!config.contains("favorites"));
+        assert_eq!(
+            read(&dir.path().join("state/favorites.toml")),
+            "favorites = [\"firefox\"]\n"
+        );
+        assert_eq!(
+            read(&dir.path().join("state/history.toml")),
+            "[firefox]\nscore = 1\n"
+        );
+        assert_eq!(read(&dir.path().join("data/waystart.toml.bak")), LEGACY);
+        assert!(!dir.path().join("data/waystart.toml").exists());
+        assert!(!dir.path().join("cache/waystart.toml").exists());
+    }
+
+    #[test]
+    fn running_again_changes_nothing() {
+        let dir = legacy_dir();
+        migrate(dir.path());
+        let config = read(&dir.path().join("config/config.toml"));
+
+        migrate(dir.path());
+        assert_eq!(read(&dir.path().join("config/config.toml")), config);
+        assert_eq!(read(&dir.path().join("data/waystart.toml.bak")), LEGACY);
+    }
+
+    #[test]
+    fn resumes_an_interrupted_migration() {
+        let dir = legacy_dir();
+        migrate(dir.path());
+        // As if the migration stopped before backing up the legacy files
+        for path in ["data/waystart.toml", "cache/waystart.toml"] {
+            let path = dir.path().join(path);
+            std::fs::rename(path.with_extension("toml.bak"), path).unwrap();
+        }
+
+        migrate(dir.path());
+        assert!(!dir.path().join("data/waystart.toml").exists());
+        assert!(!dir.path().join("cache/waystart.toml").exists());
+    }
+
+    #[test]
+    fn leaves_conflicts_untouched() {
+        let dir = legacy_dir();
+        let config = dir.path().join("config/config.toml");
+        std::fs::create_dir_all(config.parent().unwrap()).unwrap();
+        std::fs::write(&config, "terminal = \"kitty\"\n").unwrap();
+
+        migrate(dir.path());
+        assert_eq!(read(&config), "terminal = \"kitty\"\n");
+        assert!(!dir.path().join("state/favorites.toml").exists());
+        assert_eq!(read(&dir.path().join("data/waystart.toml")), LEGACY);
+    }
+}
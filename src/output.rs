@@ -0,0 +1,118 @@
+use std::process::Command;
+
+use gpui::{App, DisplayId};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+
+use crate::config::WindowOutput;
+
+/// Finds the display of the requested output, or `None` to let the compositor decide.
+pub fn find_display(output: &WindowOutput, cx: &App) -> Option<DisplayId> {
+    let name = match output {
+        WindowOutput::Default => return None,
+        WindowOutput::Focused => focused_output()?,
+        WindowOutput::Cursor => cursor_output().or_else(focused_output)?,
+        WindowOutput::Named(name) => name.clone(),
+    };
+
+    // Wayland displays are identified by a UUID derived from their output name
+    let uuid = Uuid::new_v5(&Uuid::NAMESPACE_DNS, name.as_bytes());
+    let display = cx
+        .displays()
+        .into_iter()
+        .find(|display| display.uuid().is_ok_and(|id| id == uuid));
+    if display.is_none() {
+        eprintln!("Failed to find the output {}", name);
+    }
+    display.map(|display| display.id())
+}
+
+/// Asks the compositor for the name of the output holding the focused window.
+fn focused_output() -> Option<String> {
+    if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        let monitors =
+            run_json::<Vec<HyprlandMonitor>>(Command::new("hyprctl").args(["monitors", "-j"]))?;
+        monitors.into_iter().find(|m| m.focused).map(|m| m.name)
+    } else if std::env::var_os("SWAYSOCK").is_some() {
+        let outputs =
+            run_json::<Vec<SwayOutput>>(Command::new("swaymsg").args(["-t", "get_outputs", "-r"]))?;
+        outputs.into_iter().find(|o| o.focused).map(|o| o.name)
+    } else if std::env::var_os("NIRI_SOCKET").is_some() {
+        let output =
+            run_json::<NiriOutput>(Command::new("niri").args(["msg", "--json", "focused-output"]))?;
+        Some(output.name)
+    } else {
+        eprintln!("Failed to find the focused output: unsupported compositor");
+        None
+    }
+}
+
+/// Asks the compositor for the name of the output under the cursor.
+fn cursor_output() -> Option<String> {
+    // Only Hyprland exposes the cursor position
+    std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE")?;
+
+    let cursor = run_json::<HyprlandCursor>(Command::new("hyprctl").args(["cursorpos", "-j"]))?;
+    let monitors =
+        run_json::<Vec<HyprlandMonitor>>(Command::new("hyprctl").args(["monitors", "-j"]))?;
+    monitors
+        .into_iter()
+        .find(|m| {
+            let width = m.width as f64 / m.scale;
+            let height = m.height as f64 / m.scale;
+            (m.x as f64..m.x as f64 + width).contains(&cursor.x)
+                && (m.y as f64..m.y as f64 + height).contains(&cursor.y)
+        })
+        .map(|m| m.name)
+}
+
+fn run_json<T: DeserializeOwned>(command: &mut Command) -> Option<T> {
+    let output = match command.output() {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            eprintln!(
+                "Failed to query the compositor: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return None;
+        }
+        Err(e) => {
+            eprintln!("Failed to query the compositor: {}", e);
+            return None;
+        }
+    };
+
+    serde_json::from_slice(&output.stdout)
+        .inspect_err(|e| eprintln!("Failed to parse the compositor's reply: {}", e))
+        .ok()
+}
+
+#[derive(Deserialize)]
+struct HyprlandMonitor {
+    name: String,
+    focused: bool,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    scale: f64,
+}
+
+#[derive(Deserialize)]
+struct HyprlandCursor {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Deserialize)]
+struct SwayOutput {
+    name: String,
+    #[serde(default)]
+    focused: bool,
+}
+
+#[derive(Deserialize)]
+struct NiriOutput {
+    name: String,
+}
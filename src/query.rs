@@ -0,0 +1,69 @@
+use crate::finder::Finders;
+use crate::finder::hidden::Hidden;
+use crate::finder::wifi;
+use crate::ipc::protocol::{QueryOptions, QueryResult};
+
+/// Searches the same way the window does, leaving out hidden entries.
+pub fn results(
+    options: &QueryOptions,
+    finders: &Finders,
+    hidden: &Hidden,
+) -> Result<Vec<QueryResult>, String> {
+    if let Some(mode) = &options.mode {
+        finders.check_mode(mode)?;
+    }
+
+    Ok(finders
+        .search(options.search_term.trim(), options.mode.as_deref())
+        .into_iter()
+        .filter(|entry| !hidden.contains(&entry.id()))
+        .take(options.limit.unwrap_or(usize::MAX))
+        .map(|entry| QueryResult {
+            id: entry.id().to_string(),
+            text: entry.text().to_string(),
+            description: entry
+                .description()
+                .map(|description| description.to_string()),
+            score: entry.score(),
+            finder: entry.finder().to_string(),
+        })
+        .collect())
+}
+
+/// Prints the results of a search without starting the app.
+/// Returns `false` if the mode is unknown or can't be searched without the app.
+pub fn run(options: &QueryOptions, json: bool) -> bool {
+    // Networks are only known once the daemon scanned them, so this would always come up empty
+    if options.mode.as_deref() == Some(wifi::NAME) {
+        eprintln!("The wifi mode needs the daemon, query it over IPC or D-Bus instead");
+        return false;
+    }
+
+    let results = match results(options, &Finders::new(), &Hidden::load()) {
+        Ok(results) => results,
+        Err(e) => {
+            eprintln!("{}", e);
+            return false;
+        }
+    };
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&results).expect("Failed to serialize results")
+        );
+    } else {
+        for result in results {
+            println!(
+                "{}\t{}\t{}\t{}\t{}",
+                result.score,
+                result.finder,
+                result.id,
+                result.text,
+                result.description.unwrap_or_default()
+            );
+        }
+    }
+
+    true
+}
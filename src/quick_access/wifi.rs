@@ -3,7 +3,7 @@ use gpui::{
 };
 
 use crate::config::Config;
-use crate::finder::wifi::WifiManager;
+use crate::finder::wifi::{self, WifiManager, connect};
 use crate::quick_access::QuickAccess;
 use crate::ui::elements::{Dropdown, DropdownContent, Icon};
 
@@ -50,34 +50,42 @@ impl RenderOnce for WifiQuickAccess {
             )
             .content(move |cx| {
                 let wifi = wifi.clone();
-                let enabled = wifi.enabled();
-                wifi.list()
-                    .into_iter()
+                // The networks are kept up to date by the scan running while the window is open
+                let enabled = wifi::enabled();
+                wifi::networks()
+                    .iter()
+                    .cloned()
                     .fold(DropdownContent::new(cx).w_40(), |drop, network| {
                         drop.item(
-                            network.ssid.clone(),
-                            network.ssid.clone(),
+                            network.network.ssid.clone(),
+                            network.network.ssid.clone(),
                             Some(Icon::Wifi),
-                            move |_, cx| {
-                                cx.global::<WifiManager>()
-                                    .connect(&network, nmrs::WifiSecurity::Open)
-                            },
+                            move |window, cx| connect(&network, window, cx),
                         )
                     })
                     .separate()
                     .item(
                         "quick-wifi-toggle",
-                        if enabled {
-                            "Disable Wi-Fi"
-                        } else {
-                            "Enable Wi-Fi"
+                        match enabled {
+                            Some(true) => "Disable Wi-Fi",
+                            Some(false) => "Enable Wi-Fi",
+                            None => "Toggle Wi-Fi",
                         },
                         Some(Icon::Restart),
-                        move |window, _| match wifi.enable(!enabled) {
-                            Ok(_) => window.remove_window(),
-                            Err(e) => {
-                                eprintln!("Failed to toggle wifi: {}", e);
-                            }
+                        move |window, cx| {
+                            let wifi = wifi.clone();
+                            cx.spawn(async move |_| {
+                                // The state is unknown until the first scan is done
+                                let enabled = match enabled {
+                                    Some(enabled) => enabled,
+                                    None => wifi.enabled().await,
+                                };
+                                if let Err(e) = wifi.enable(!enabled).await {
+                                    eprintln!("Failed to toggle wifi: {}", e);
+                                }
+                            })
+                            .detach();
+                            window.remove_window();
                         },
                     )
             })
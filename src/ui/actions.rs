@@ -2,5 +2,12 @@ use gpui::actions;
 
 actions!(
     waystart,
-    [SelectPrev, SelectNext, ExecuteEntry, ToggleFavorite, Close]
+    [
+        SelectPrev,
+        SelectNext,
+        ExecuteEntry,
+        ToggleFavorite,
+        OpenActions,
+        Close
+    ]
 );
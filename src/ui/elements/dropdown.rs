@@ -58,13 +58,12 @@ impl DropdownContent {
     where
         A: 'static + Fn(&mut Window, &mut App),
     {
-        self.items.push(DropdownItem {
-            id: id.into(),
-            label: label.into(),
-            icon,
-            action: Rc::new(action),
-            separate: false,
-        });
+        self.items.push(DropdownItem::new(id, label, icon, action));
+        self
+    }
+
+    pub fn items(mut self, items: impl IntoIterator<Item = DropdownItem>) -> Self {
+        self.items.extend(items);
         self
     }
 
@@ -179,7 +178,7 @@ impl Render for DropdownContent {
     }
 }
 
-struct DropdownItem {
+pub struct DropdownItem {
     id: ElementId,
     label: SharedString,
     icon: Option<Icon>,
@@ -188,6 +187,26 @@ struct DropdownItem {
 }
 type ItemAction = dyn Fn(&mut Window, &mut App);
 
+impl DropdownItem {
+    pub fn new<A>(
+        id: impl Into<ElementId>,
+        label: impl Into<SharedString>,
+        icon: Option<Icon>,
+        action: A,
+    ) -> Self
+    where
+        A: 'static + Fn(&mut Window, &mut App),
+    {
+        Self {
+            id: id.into(),
+            label: label.into(),
+            icon,
+            action: Rc::new(action),
+            separate: false,
+        }
+    }
+}
+
 pub struct Dropdown<M: ManagedView> {
     id: ElementId,
 
@@ -1,8 +1,10 @@
+use std::ops::Range;
 use std::rc::Rc;
 
 use gpui::{
-    App, ImageSource, InteractiveElement, IntoElement, ObjectFit, ParentElement, RenderOnce,
-    Styled, StyledImage, TextOverflow, Window, div, img, prelude::FluentBuilder,
+    App, HighlightStyle, ImageSource, InteractiveElement, IntoElement, ObjectFit, ParentElement,
+    RenderOnce, SharedString, Styled, StyledImage, StyledText, TextOverflow, Window, div, img,
+    prelude::FluentBuilder,
 };
 
 use crate::config::Config;
@@ -33,6 +35,11 @@ impl EntryButton {
 impl RenderOnce for EntryButton {
     fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
         let config = cx.global::<Config>();
+        let highlights = self.entry.highlights();
+        let highlight = HighlightStyle {
+            color: Some(config.theme.accent.into()),
+            ..Default::default()
+        };
 
         div()
             .flex()
@@ -59,7 +66,7 @@ impl RenderOnce for EntryButton {
                         .object_fit(ObjectFit::Contain),
                 )
             })
-            .child(self.entry.text().clone())
+            .child(highlighted(self.entry.text(), highlights.text, highlight))
             .when(self.selected, |this| {
                 this.bg(config.theme.muted).when_some(
                     self.entry.description(),
@@ -71,10 +78,19 @@ impl RenderOnce for EntryButton {
                                 .when(self.selected, |this| this.bg(config.theme.muted))
                                 .text_overflow(TextOverflow::Truncate("...".into()))
                                 .when(!self.favorite, |this| this.child(" — "))
-                                .child(description.clone()),
+                                .child(highlighted(
+                                    description,
+                                    highlights.description.clone(),
+                                    highlight,
+                                )),
                         )
                     },
                 )
             })
     }
 }
+
+/// Renders `text` with the characters in `ranges` drawn in the given style.
+fn highlighted(text: SharedString, ranges: Vec<Range<usize>>, style: HighlightStyle) -> StyledText {
+    StyledText::new(text).with_highlights(ranges.into_iter().map(|range| (range, style)))
+}
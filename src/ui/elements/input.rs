@@ -30,6 +30,7 @@ actions!(
     ]
 );
 const CONTEXT: &str = "TextInput";
+const MASK: char = '•';
 
 pub(super) fn init(cx: &mut App) {
     cx.bind_keys([
@@ -53,6 +54,7 @@ pub struct TextInput {
     focus_handle: FocusHandle,
     content: SharedString,
     placeholder: SharedString,
+    masked: bool,
     selected_range: Range<usize>,
     selection_reversed: bool,
     marked_range: Option<Range<usize>>,
@@ -67,6 +69,7 @@ impl TextInput {
             focus_handle,
             content: SharedString::default(),
             placeholder: SharedString::default(),
+            masked: false,
             selected_range: 0..0,
             selection_reversed: false,
             marked_range: None,
@@ -81,10 +84,23 @@ impl TextInput {
         self
     }
 
+    /// Hide the content behind a mask, for passwords.
+    pub fn masked(mut self, masked: bool) -> Self {
+        self.masked = masked;
+        self
+    }
+
     pub fn content(&self) -> &SharedString {
         &self.content
     }
 
+    /// Replaces the content and moves the cursor to its end.
+    pub fn set_content(&mut self, content: impl Into<SharedString>) {
+        self.reset();
+        self.content = content.into();
+        self.selected_range = self.content.len()..self.content.len();
+    }
+
     pub fn reset(&mut self) {
         self.content = SharedString::default();
         self.selected_range = 0..0;
@@ -188,7 +204,7 @@ impl TextInput {
     }
 
     fn copy(&mut self, _: &Copy, _: &mut Window, cx: &mut Context<Self>) {
-        if !self.selected_range.is_empty() {
+        if !self.selected_range.is_empty() && !self.masked {
             cx.write_to_clipboard(ClipboardItem::new_string(
                 self.content[self.selected_range.clone()].to_string(),
             ));
@@ -196,7 +212,7 @@ impl TextInput {
     }
 
     fn cut(&mut self, _: &Cut, window: &mut Window, cx: &mut Context<Self>) {
-        if !self.selected_range.is_empty() {
+        if !self.selected_range.is_empty() && !self.masked {
             cx.write_to_clipboard(ClipboardItem::new_string(
                 self.content[self.selected_range.clone()].to_string(),
             ));
@@ -232,7 +248,39 @@ impl TextInput {
         if position.y > bounds.bottom() {
             return self.content.len();
         }
-        line.closest_index_for_x(position.x - bounds.left())
+        self.offset_from_display(line.closest_index_for_x(position.x - bounds.left()))
+    }
+
+    /// The text shown in the input, where the content is replaced by the mask if needed.
+    fn display_text(&self) -> SharedString {
+        if self.masked {
+            MASK.to_string().repeat(self.content.chars().count()).into()
+        } else {
+            self.content.clone()
+        }
+    }
+
+    fn offset_to_display(&self, offset: usize) -> usize {
+        if self.masked {
+            self.content[..offset].chars().count() * MASK.len_utf8()
+        } else {
+            offset
+        }
+    }
+
+    fn offset_from_display(&self, offset: usize) -> usize {
+        if self.masked {
+            self.content
+                .char_indices()
+                .nth(offset / MASK.len_utf8())
+                .map_or(self.content.len(), |(idx, _)| idx)
+        } else {
+            offset
+        }
+    }
+
+    fn range_to_display(&self, range: &Range<usize>) -> Range<usize> {
+        self.offset_to_display(range.start)..self.offset_to_display(range.end)
     }
 
     fn select_to(&mut self, offset: usize, cx: &mut Context<Self>) {
@@ -401,7 +449,7 @@ impl EntityInputHandler for TextInput {
         _cx: &mut Context<Self>,
     ) -> Option<Bounds<Pixels>> {
         let last_layout = self.last_layout.as_ref()?;
-        let range = self.range_from_utf16(&range_utf16);
+        let range = self.range_to_display(&self.range_from_utf16(&range_utf16));
         Some(Bounds::from_corners(
             point(
                 bounds.left() + last_layout.x_for_index(range.start),
@@ -423,9 +471,9 @@ impl EntityInputHandler for TextInput {
         let line_point = self.last_bounds?.localize(&point)?;
         let last_layout = self.last_layout.as_ref()?;
 
-        assert_eq!(last_layout.text, self.content);
+        assert_eq!(last_layout.text, self.display_text());
         let utf8_index = last_layout.index_for_x(point.x - line_point.x)?;
-        Some(self.offset_to_utf16(utf8_index))
+        Some(self.offset_to_utf16(self.offset_from_display(utf8_index)))
     }
 }
 
@@ -485,17 +533,21 @@ impl Element for TextElement {
 
         let input = self.input.read(cx);
         let content = input.content.clone();
-        let selected_range = input.selected_range.clone();
-        let cursor = input.cursor_offset();
+        let selected_range = input.range_to_display(&input.selected_range);
+        let cursor = input.offset_to_display(input.cursor_offset());
         let style = window.text_style();
 
         let (display_text, text_color) = if content.is_empty() {
             (input.placeholder.clone(), config.theme.muted.into())
         } else {
-            (content.clone(), style.color)
+            (input.display_text(), style.color)
         };
 
-        let runs = if let Some(marked_range) = input.marked_range.as_ref() {
+        let marked_range = input
+            .marked_range
+            .as_ref()
+            .map(|range| input.range_to_display(range));
+        let runs = if let Some(marked_range) = marked_range.as_ref() {
             vec![
                 TextRun {
                     len: marked_range.start,
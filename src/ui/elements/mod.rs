@@ -7,7 +7,7 @@ pub mod input;
 pub mod separator;
 pub mod shortcut;
 
-pub use dropdown::{Dropdown, DropdownContent};
+pub use dropdown::{Dropdown, DropdownContent, DropdownItem};
 pub use entry_button::EntryButton;
 pub use icon::Icon;
 pub use input::TextInput;
@@ -0,0 +1,81 @@
+use std::rc::Rc;
+
+use gpui::{
+    App, AppContext, Context, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable,
+    IntoElement, ParentElement, Render, Styled, Window, div,
+};
+
+use crate::config::Config;
+use crate::finder::Entry;
+use crate::finder::favorites::Favorites;
+use crate::ui::elements::{DropdownContent, DropdownItem};
+use crate::ui::pages::{hide, set_favorite};
+
+pub struct ActionsPage {
+    entry: Rc<dyn Entry>,
+    content: Entity<DropdownContent>,
+}
+
+impl ActionsPage {
+    pub fn new(entry: Rc<dyn Entry>, cx: &mut Context<Self>) -> Self {
+        let mut items = entry.actions();
+        if entry.can_favorite() {
+            let id = entry.id();
+            let is_favorite = cx.global::<Favorites>().contains(&id);
+            items.push(DropdownItem::new(
+                "action-favorite",
+                if is_favorite {
+                    "Remove from favorites"
+                } else {
+                    "Add to favorites"
+                },
+                None,
+                {
+                    let id = id.clone();
+                    move |_, cx| set_favorite(id.clone(), !is_favorite, cx)
+                },
+            ));
+            items.push(DropdownItem::new(
+                "action-hide",
+                "Hide from results",
+                None,
+                move |_, cx| hide(id.clone(), cx),
+            ));
+        }
+
+        let content = cx.new(|cx| DropdownContent::new(cx).w_full().border_0().items(items));
+        cx.subscribe(&content, |_, _, _: &DismissEvent, cx| cx.emit(DismissEvent))
+            .detach();
+
+        ActionsPage { entry, content }
+    }
+}
+
+impl EventEmitter<DismissEvent> for ActionsPage {}
+
+impl Focusable for ActionsPage {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.content.focus_handle(cx)
+    }
+}
+
+impl Render for ActionsPage {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let config = cx.global::<Config>();
+
+        div()
+            .flex_grow()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .px_2()
+            .child(
+                div()
+                    .px_5()
+                    .py_1()
+                    .text_color(config.theme.muted_foreground)
+                    .child(format!("Actions for {}", self.entry.text())),
+            )
+            .child(div().px_2().child(self.content.clone()))
+    }
+}
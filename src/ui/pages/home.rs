@@ -10,6 +10,7 @@ use crate::config::Config;
 use crate::finder::favorites::Favorites;
 use crate::finder::{Entry, Finders};
 use crate::ui::elements::EntryButton;
+use crate::ui::pages::{execute, set_favorite, visible};
 
 pub struct HomePage {
     selected: usize,
@@ -21,7 +22,7 @@ impl HomePage {
     pub fn new(cx: &mut Context<Self>) -> Self {
         HomePage {
             selected: 0,
-            entries: cx.global::<Finders>().default_entries(),
+            entries: visible(cx.global::<Finders>().default_entries(), cx),
             list_scroll_handle: UniformListScrollHandle::new(),
         }
     }
@@ -50,15 +51,19 @@ impl HomePage {
 
     pub(super) fn execute_entry<A>(&mut self, _: &A, window: &mut Window, cx: &mut Context<Self>) {
         if let Some(entry) = self.entries.get(self.selected) {
-            entry.execute(window, cx);
+            execute(entry, None, window, cx);
         };
     }
 
+    pub(super) fn selected_entry(&self) -> Option<Rc<dyn Entry>> {
+        self.entries.get(self.selected).cloned()
+    }
+
     pub(super) fn toggle_favorite(&self, cx: &mut Context<Self>) {
         if let Some(entry) = self.entries.get(self.selected)
             && entry.can_favorite()
         {
-            cx.global_mut::<Favorites>().insert(entry.id().clone());
+            set_favorite(entry.id(), true, cx);
         }
     }
 }
@@ -1,30 +1,55 @@
-use gpui::{App, AppContext, Entity, Global, IntoElement, RenderOnce, Window};
+use std::rc::Rc;
 
+use gpui::{App, AppContext, Entity, Global, IntoElement, RenderOnce, SharedString, Window};
+
+use crate::finder::desktop::frequency::DESKTOP_FREQUENCIES;
+use crate::finder::favorites::Favorites;
+use crate::finder::hidden::Hidden;
+use crate::finder::saves::Saves;
+use crate::finder::{Entry, Finders, desktop};
+use crate::ipc::events;
+use crate::ipc::protocol::Event;
+
+mod actions;
 mod home;
+mod password;
 mod search;
 
+pub use actions::ActionsPage;
 pub use home::HomePage;
+pub use password::PasswordPage;
 pub use search::SearchPage;
 
 #[derive(Clone, IntoElement)]
 pub enum Page {
     Home(Entity<HomePage>),
     Search(Entity<SearchPage>),
+    Actions(Entity<ActionsPage>),
+    Password(Entity<PasswordPage>),
 }
 
 impl Global for Page {}
 
 impl Page {
+    pub fn new(search_term: &str, cx: &mut App) -> Self {
+        if show_home(search_term, cx) {
+            Page::Home(cx.new(HomePage::new))
+        } else {
+            Page::Search(cx.new(|cx| SearchPage::new(search_term, cx)))
+        }
+    }
+
     pub fn on_search(&mut self, search_term: &str, cx: &mut App) {
-        if search_term.is_empty() && matches!(self, Page::Search(_)) {
+        let home = show_home(search_term, cx);
+        if home && matches!(self, Page::Search(_)) {
             // Switch to home page when search is cleared from search page
             *self = Page::Home(cx.new(HomePage::new));
-        } else if !search_term.is_empty() && matches!(self, Page::Home(_)) {
+        } else if !home && matches!(self, Page::Home(_)) {
             // Switch to search page when searching from home page
             *self = Page::Search(cx.new(|cx| SearchPage::new(search_term, cx)));
         } else {
             match self {
-                Page::Home(_) => {}
+                Page::Home(_) | Page::Actions(_) | Page::Password(_) => {}
                 Page::Search(page) => page.update(cx, |page, cx| page.on_search(search_term, cx)),
             }
         }
@@ -34,6 +59,7 @@ impl Page {
         match self {
             Page::Home(page) => page.update(cx, |page, cx| page.select_prev(cx)),
             Page::Search(page) => page.update(cx, |page, cx| page.select_prev(cx)),
+            Page::Actions(_) | Page::Password(_) => {}
         }
     }
 
@@ -41,6 +67,7 @@ impl Page {
         match self {
             Page::Home(page) => page.update(cx, |page, cx| page.select_next(cx)),
             Page::Search(page) => page.update(cx, |page, cx| page.select_next(cx)),
+            Page::Actions(_) | Page::Password(_) => {}
         }
     }
 
@@ -48,6 +75,8 @@ impl Page {
         match self {
             Page::Home(page) => page.update(cx, |page, cx| page.execute_entry(&(), window, cx)),
             Page::Search(page) => page.update(cx, |page, cx| page.execute_entry(&(), window, cx)),
+            Page::Actions(_) => {}
+            Page::Password(page) => page.update(cx, |page, cx| page.submit(window, cx)),
         }
     }
 
@@ -55,6 +84,15 @@ impl Page {
         match self {
             Page::Home(page) => page.update(cx, |page, cx| page.toggle_favorite(cx)),
             Page::Search(page) => page.update(cx, |page, cx| page.toggle_favorite(cx)),
+            Page::Actions(_) | Page::Password(_) => {}
+        }
+    }
+
+    pub fn selected_entry(&self, cx: &App) -> Option<Rc<dyn Entry>> {
+        match self {
+            Page::Home(page) => page.read(cx).selected_entry(),
+            Page::Search(page) => page.read(cx).selected_entry(),
+            Page::Actions(_) | Page::Password(_) => None,
         }
     }
 }
@@ -64,6 +102,74 @@ impl RenderOnce for Page {
         match self {
             Page::Home(page) => page.clone().into_any_element(),
             Page::Search(page) => page.clone().into_any_element(),
+            Page::Actions(page) => page.clone().into_any_element(),
+            Page::Password(page) => page.clone().into_any_element(),
         }
     }
 }
+
+/// The home page is shown when nothing is searched, unless the results are restricted to a single finder.
+fn show_home(search_term: &str, cx: &App) -> bool {
+    search_term.is_empty() && cx.global::<Finders>().mode().is_none()
+}
+
+/// Executes `entry`, letting subscribers know about it.
+///
+/// `query` is the search term that found the entry, if any, which learns to rank it higher.
+fn execute(entry: &Rc<dyn Entry>, query: Option<&str>, window: &mut Window, cx: &mut App) {
+    if let Some(query) = query
+        && entry.finder() == desktop::NAME
+    {
+        DESKTOP_FREQUENCIES.increment_query_frequency(query, &entry.id());
+    }
+
+    events::emit(
+        Event::EntryExecuted {
+            entry: entry.id().to_string(),
+            finder: entry.finder().to_string(),
+        },
+        cx,
+    );
+    entry.execute(window, cx);
+}
+
+/// Adds or removes `id` from the favorites, letting subscribers know if it changed.
+fn set_favorite(id: SharedString, favorite: bool, cx: &mut App) {
+    let favorites = cx.global_mut::<Favorites>();
+    let changed = if favorite {
+        favorites.insert(id.clone())
+    } else {
+        favorites.remove(&id)
+    };
+
+    if changed {
+        // Save right away, so reloading the favorites from disk doesn't lose the change
+        let favorites = favorites.clone();
+        Saves::spawn(async move { favorites.save().await }, cx);
+        events::emit(
+            Event::FavoriteToggled {
+                entry: id.to_string(),
+                favorite,
+            },
+            cx,
+        );
+    }
+}
+
+/// Hides `id` from the results, removing it from the favorites.
+fn hide(id: SharedString, cx: &mut App) {
+    set_favorite(id.clone(), false, cx);
+
+    let hidden = cx.global_mut::<Hidden>();
+    if hidden.insert(id) {
+        let hidden = hidden.clone();
+        Saves::spawn(async move { hidden.save().await }, cx);
+    }
+}
+
+/// Removes the entries the user chose to hide from the results.
+fn visible(mut entries: Vec<Rc<dyn Entry>>, cx: &App) -> Vec<Rc<dyn Entry>> {
+    let hidden = cx.global::<Hidden>();
+    entries.retain(|entry| !hidden.contains(&entry.id()));
+    entries
+}
@@ -0,0 +1,151 @@
+use gpui::prelude::FluentBuilder;
+use gpui::{
+    App, AppContext, Context, Entity, FocusHandle, Focusable, IntoElement, ParentElement, Render,
+    SharedString, Styled, Window, div,
+};
+
+use crate::config::Config;
+use crate::finder::wifi::WifiManager;
+use crate::ui::elements::TextInput;
+
+pub struct PasswordPage {
+    network: nmrs::Network,
+    input: Entity<TextInput>,
+    state: PasswordState,
+}
+
+enum PasswordState {
+    Idle,
+    Connecting,
+    Failed(SharedString),
+}
+
+impl PasswordPage {
+    pub fn new(network: nmrs::Network, cx: &mut Context<Self>) -> Self {
+        let input = cx.new(|cx| {
+            TextInput::new(cx.focus_handle())
+                .placeholder("Password")
+                .masked(true)
+        });
+
+        PasswordPage {
+            network,
+            input,
+            state: PasswordState::Idle,
+        }
+    }
+
+    /// Connects to a known or open network right away, asking for the password if it fails.
+    pub fn connecting(network: nmrs::Network, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let mut page = Self::new(network, cx);
+        // NetworkManager reuses the saved credentials of known networks
+        page.connect(nmrs::WifiSecurity::Open, window, cx);
+        page
+    }
+
+    pub(super) fn submit(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if matches!(self.state, PasswordState::Connecting) {
+            return;
+        }
+        let security = if self.network.is_psk {
+            let psk = self.input.read(cx).content().to_string();
+            if psk.is_empty() {
+                return;
+            }
+            nmrs::WifiSecurity::WpaPsk { psk }
+        } else {
+            nmrs::WifiSecurity::Open
+        };
+
+        self.connect(security, window, cx);
+    }
+
+    fn connect(
+        &mut self,
+        security: nmrs::WifiSecurity,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(wifi) = cx.try_global::<WifiManager>().cloned() else {
+            self.state = PasswordState::Failed("NetworkManager isn't available".into());
+            cx.notify();
+            return;
+        };
+
+        self.state = PasswordState::Connecting;
+        cx.notify();
+
+        let network = self.network.clone();
+        cx.spawn_in(window, async move |this, cx| {
+            match wifi.connect(&network, security).await {
+                Ok(()) => {
+                    this.update_in(cx, |_, window, _| window.remove_window())
+                        .ok();
+                }
+                Err(e) => {
+                    this.update(cx, |this, cx| {
+                        this.state = PasswordState::Failed(e.to_string().into());
+                        this.input.update(cx, |input, cx| {
+                            input.reset();
+                            cx.notify();
+                        });
+                        cx.notify();
+                    })
+                    .ok();
+                }
+            }
+        })
+        .detach();
+    }
+}
+
+impl Focusable for PasswordPage {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.input.focus_handle(cx)
+    }
+}
+
+impl Render for PasswordPage {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let config = cx.global::<Config>();
+
+        div()
+            .flex_grow()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .px_2()
+            .child(
+                div()
+                    .px_5()
+                    .py_1()
+                    .text_color(config.theme.muted_foreground)
+                    .child(format!("Connect to {}", self.network.ssid)),
+            )
+            .when(self.network.is_psk, |this| {
+                this.child(
+                    div()
+                        .mx_4()
+                        .flex()
+                        .border_1()
+                        .border_color(config.theme.border)
+                        .rounded_lg()
+                        .overflow_hidden()
+                        .child(self.input.clone()),
+                )
+            })
+            .child(div().px_5().py_1().map(|this| {
+                match self.state {
+                    PasswordState::Idle => this
+                        .text_color(config.theme.muted_foreground)
+                        .child("Press enter to connect, or escape to go back."),
+                    PasswordState::Connecting => this
+                        .text_color(config.theme.muted_foreground)
+                        .child("Connecting..."),
+                    PasswordState::Failed(ref error) => this
+                        .text_color(config.theme.accent)
+                        .child(format!("Failed to connect: {}. Please try again.", error)),
+                }
+            }))
+    }
+}
@@ -1,35 +1,84 @@
+use std::cmp::Reverse;
 use std::rc::Rc;
 
 use gpui::{
     Context, InteractiveElement, IntoElement, ParentElement, Render, ScrollStrategy,
-    StatefulInteractiveElement, Styled, UniformListScrollHandle, Window, div, uniform_list,
+    StatefulInteractiveElement, Styled, Task, UniformListScrollHandle, Window, div, uniform_list,
 };
 
 use crate::config::Config;
-use crate::finder::favorites::Favorites;
 use crate::finder::{Entry, Finders};
 use crate::ui::elements::EntryButton;
+use crate::ui::pages::{execute, set_favorite, visible};
 
 pub struct SearchPage {
     selected: usize,
     entries: Vec<Rc<dyn Entry>>,
+    /// The search term that found the shown entries.
+    search_term: String,
     list_scroll_handle: UniformListScrollHandle,
+    /// The search filling the results, cancelled when a new one starts.
+    search: Task<()>,
 }
 
 impl SearchPage {
     pub fn new(search_term: &str, cx: &mut Context<Self>) -> Self {
-        SearchPage {
+        let mut page = SearchPage {
             selected: 0,
-            entries: cx.global::<Finders>().filtered_entries(search_term),
+            entries: Vec::new(),
+            search_term: String::new(),
             list_scroll_handle: UniformListScrollHandle::new(),
-        }
+            search: Task::ready(()),
+        };
+        page.on_search(search_term, cx);
+        page
     }
 
     pub(super) fn on_search(&mut self, search_term: &str, cx: &mut Context<Self>) {
-        self.entries = cx.global::<Finders>().filtered_entries(search_term);
-        self.selected = 0;
+        let mut search = cx
+            .global::<Finders>()
+            .spawn_search(search_term, cx.background_executor());
+        let search_term = search_term.to_string();
+
+        self.search = cx.spawn(async move |this, cx| {
+            // The previous results stay until the first finder is done, to avoid flickering
+            let mut first = true;
+            let mut entries = Vec::new();
+            while let Some(found) = search.next().await {
+                entries.extend(found);
+                entries.sort_by_key(|entry| Reverse(entry.score()));
+                let shown = this.update(cx, |page, cx| {
+                    page.search_term.clone_from(&search_term);
+                    page.show_entries(visible(entries.clone(), cx), first, cx)
+                });
+                if shown.is_err() {
+                    return;
+                }
+                first = false;
+            }
+
+            if first {
+                this.update(cx, |page, cx| {
+                    page.search_term = search_term;
+                    page.show_entries(Vec::new(), true, cx)
+                })
+                .ok();
+            }
+        });
+    }
+
+    /// Shows `entries`, keeping the selected entry when results are added to the current search.
+    fn show_entries(&mut self, entries: Vec<Rc<dyn Entry>>, reset: bool, cx: &mut Context<Self>) {
+        let selected = (!reset)
+            .then(|| self.selected_entry())
+            .flatten()
+            .and_then(|selected| entries.iter().position(|entry| entry.id() == selected.id()));
+
+        self.entries = entries;
+        self.selected = selected.unwrap_or(0);
         self.list_scroll_handle
-            .scroll_to_item(0, ScrollStrategy::Top);
+            .scroll_to_item(self.selected, ScrollStrategy::Top);
+        cx.notify();
     }
 
     pub(super) fn select_prev(&mut self, cx: &mut Context<Self>) {
@@ -56,15 +105,19 @@ impl SearchPage {
 
     pub(super) fn execute_entry<A>(&mut self, _: &A, window: &mut Window, cx: &mut Context<Self>) {
         if let Some(entry) = self.entries.get(self.selected) {
-            entry.execute(window, cx);
+            execute(entry, Some(&self.search_term), window, cx);
         };
     }
 
+    pub(super) fn selected_entry(&self) -> Option<Rc<dyn Entry>> {
+        self.entries.get(self.selected).cloned()
+    }
+
     pub(super) fn toggle_favorite(&self, cx: &mut Context<Self>) {
         if let Some(entry) = self.entries.get(self.selected)
             && entry.can_favorite()
         {
-            cx.global_mut::<Favorites>().insert(entry.id().clone());
+            set_favorite(entry.id(), true, cx);
         }
     }
 }
@@ -1,13 +1,17 @@
 use gpui::{
-    App, AppContext, Context, Entity, FocusHandle, Focusable, InteractiveElement, IntoElement,
-    KeyBinding, ParentElement, Render, Styled, Window, div,
+    App, AppContext, Context, DismissEvent, Entity, FocusHandle, Focusable, InteractiveElement,
+    IntoElement, KeyBinding, ParentElement, Render, Styled, Window, div,
 };
 
 use crate::config::Config;
+use crate::ipc::events;
+use crate::ipc::protocol::Event;
 use crate::quick_access::Quicks;
-use crate::ui::actions::{Close, ExecuteEntry, SelectNext, SelectPrev, ToggleFavorite};
+use crate::ui::actions::{
+    Close, ExecuteEntry, OpenActions, SelectNext, SelectPrev, ToggleFavorite,
+};
 use crate::ui::elements::{Icon, Separator, Shortcut, TextInput};
-use crate::ui::pages::{HomePage, Page};
+use crate::ui::pages::{ActionsPage, HomePage, Page, PasswordPage};
 
 const CONTEXT: &str = "Waystart";
 
@@ -19,6 +23,7 @@ pub(super) fn init(cx: &mut App) {
         KeyBinding::new("tab", SelectNext, Some(CONTEXT)),
         KeyBinding::new("enter", ExecuteEntry, Some(CONTEXT)),
         KeyBinding::new("secondary-d", ToggleFavorite, Some(CONTEXT)),
+        KeyBinding::new("secondary-k", OpenActions, Some(CONTEXT)),
         KeyBinding::new("escape", Close, Some(CONTEXT)),
     ]);
 }
@@ -27,6 +32,8 @@ pub struct Waystart {
     page: Page,
     focus_handle: FocusHandle,
     search_bar: Entity<TextInput>,
+    /// The last search term, to tell subscribers when it changes.
+    search_term: String,
 }
 
 impl Waystart {
@@ -43,21 +50,69 @@ impl Waystart {
             page: Page::Home(cx.new(HomePage::new)),
             focus_handle,
             search_bar,
+            search_term: String::new(),
         }
     }
 
-    pub fn reset_search(&mut self, cx: &mut Context<Self>) {
+    pub fn reset_search(&mut self, query: &str, cx: &mut Context<Self>) {
         self.search_bar
-            .update(cx, |search_bar, _| search_bar.reset());
+            .update(cx, |search_bar, _| search_bar.set_content(query));
+        // Rebuild the page so the recents reflect the latest launches
+        self.page = Page::new(query.trim(), cx);
+        self.set_search_term(query.trim(), cx);
+    }
+
+    /// Runs the current search again, to pick up entries that changed in the background.
+    pub fn refresh_results(&mut self, cx: &mut Context<Self>) {
+        self.filter_results(cx);
+        cx.notify();
     }
 
     fn filter_results(&mut self, cx: &mut Context<Self>) {
         let search_term = self.search_bar.read(cx).content().trim().to_string();
         self.page.on_search(&search_term, cx);
+        self.set_search_term(&search_term, cx);
+    }
+
+    fn set_search_term(&mut self, search_term: &str, cx: &mut Context<Self>) {
+        if self.search_term != search_term {
+            self.search_term = search_term.to_string();
+            events::emit(
+                Event::QueryChanged {
+                    query: self.search_term.clone(),
+                },
+                cx,
+            );
+        }
+    }
+
+    /// Asks the user for the password of `network` before connecting to it.
+    pub fn prompt_password(
+        &mut self,
+        network: nmrs::Network,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let page = cx.new(|cx| PasswordPage::new(network, cx));
+        window.focus(&page.focus_handle(cx), cx);
+        self.page = Page::Password(page);
+        cx.notify();
+    }
+
+    /// Connects to a known or open network, staying open to report the outcome.
+    pub fn connect(&mut self, network: nmrs::Network, window: &mut Window, cx: &mut Context<Self>) {
+        let page = cx.new(|cx| PasswordPage::connecting(network, window, cx));
+        window.focus(&page.focus_handle(cx), cx);
+        self.page = Page::Password(page);
+        cx.notify();
     }
 
-    fn on_close(_: &Close, window: &mut Window, _cx: &mut App) {
-        window.remove_window();
+    fn on_close(&mut self, _: &Close, window: &mut Window, cx: &mut Context<Self>) {
+        if matches!(self.page, Page::Password(_)) {
+            self.back_to_results(window, cx);
+        } else {
+            window.remove_window();
+        }
     }
 
     fn select_prev<A>(&mut self, _: &A, _window: &mut Window, cx: &mut Context<Self>) {
@@ -75,6 +130,29 @@ impl Waystart {
     fn toggle_favorite<A>(&mut self, _: &A, _window: &mut Window, cx: &mut Context<Self>) {
         self.page.toggle_favorite(cx);
     }
+
+    fn open_actions<A>(&mut self, _: &A, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(entry) = self.page.selected_entry(cx) else {
+            return;
+        };
+
+        let page = cx.new(|cx| ActionsPage::new(entry, cx));
+        cx.subscribe_in(&page, window, |this, _, _: &DismissEvent, window, cx| {
+            this.back_to_results(window, cx)
+        })
+        .detach();
+
+        window.focus(&page.focus_handle(cx), cx);
+        self.page = Page::Actions(page);
+        cx.notify();
+    }
+
+    fn back_to_results(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let search_term = self.search_bar.read(cx).content().trim().to_string();
+        self.page = Page::new(&search_term, cx);
+        window.focus(&self.focus_handle, cx);
+        cx.notify();
+    }
 }
 
 impl Render for Waystart {
@@ -95,11 +173,12 @@ impl Render for Waystart {
             .overflow_hidden()
             .track_focus(&self.focus_handle(cx))
             .key_context(CONTEXT)
-            .on_action::<Close>(Self::on_close)
+            .on_action::<Close>(cx.listener(Self::on_close))
             .on_action::<SelectPrev>(cx.listener(Self::select_prev))
             .on_action::<SelectNext>(cx.listener(Self::select_next))
             .on_action::<ExecuteEntry>(cx.listener(Self::execute_entry))
             .on_action::<ToggleFavorite>(cx.listener(Self::toggle_favorite))
+            .on_action::<OpenActions>(cx.listener(Self::open_actions))
             .child(
                 div()
                     .h_16()
@@ -126,6 +205,9 @@ impl Render for Waystart {
                             .flex()
                             .items_center()
                             .gap_1()
+                            .child("Actions")
+                            .child(Shortcut::new("Ctrl K"))
+                            .child(div().w_2())
                             .child("Open")
                             .child(Shortcut::new("↵")),
                     ),
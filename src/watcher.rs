@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use inotify::{Inotify, WatchMask};
+
+/// Watches the given directories for changes on a dedicated thread.
+///
+/// The returned channel receives the path of every file that is created, written, moved or
+/// deleted in one of the directories. The thread stops once the receiver is dropped.
+pub fn watch(dirs: &[PathBuf]) -> std::io::Result<smol::channel::Receiver<PathBuf>> {
+    let mut inotify = Inotify::init()?;
+    let mut watched = HashMap::new();
+    for dir in dirs {
+        let wd = inotify.watches().add(
+            dir,
+            WatchMask::CREATE
+                | WatchMask::CLOSE_WRITE
+                | WatchMask::MOVED_TO
+                | WatchMask::MOVED_FROM
+                | WatchMask::DELETE,
+        )?;
+        watched.insert(wd, dir.clone());
+    }
+
+    let (tx, rx) = smol::channel::unbounded();
+    std::thread::spawn(move || {
+        let mut buffer = [0; 4096];
+        loop {
+            let events = match inotify.read_events_blocking(&mut buffer) {
+                Ok(events) => events,
+                Err(err) => {
+                    eprintln!("Failed to read file system events: {}", err);
+                    return;
+                }
+            };
+
+            for event in events {
+                let (Some(dir), Some(name)) = (watched.get(&event.wd), event.name) else {
+                    continue;
+                };
+                if tx.send_blocking(dir.join(name)).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(rx)
+}
+
+/// Returns the symlinks traversed when resolving `path`, such as the links of a nix profile.
+///
+/// Watches follow these links, so they must be watched separately to notice when they change.
+pub fn symlinks_in(path: &Path) -> Vec<PathBuf> {
+    let mut links = Vec::new();
+    let mut path = path.to_path_buf();
+
+    // Bound the resolution, like the kernel does, in case of a symlink loop
+    'resolve: for _ in 0..40 {
+        for ancestor in path.ancestors().collect::<Vec<_>>().into_iter().rev() {
+            let Ok(target) = std::fs::read_link(ancestor) else {
+                continue;
+            };
+            let Ok(rest) = path.strip_prefix(ancestor) else {
+                break 'resolve;
+            };
+
+            let parent = ancestor.parent().unwrap_or(Path::new("/"));
+            let resolved = parent.join(target).join(rest);
+            links.push(ancestor.to_path_buf());
+            path = resolved;
+            continue 'resolve;
+        }
+        break;
+    }
+
+    links
+}